@@ -38,7 +38,8 @@
 //! 4.2.5
 //! ```
 //!
-//! Newlines are always stripped when reading files.
+//! Newlines are always stripped when reading files, and a file containing
+//! more than one different version is rejected.
 //!
 //! Configuration files are usable through the [`Config`] tuple struct, which
 //! wraps a [`HaxeVersion`] tuple struct as data and provides configuration
@@ -77,37 +78,872 @@
 //! # }
 //! ```
 
+use flate2::{Compression, write::GzEncoder};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
 use std::env;
+use std::ffi::{OsStr, OsString};
 use std::fs;
-use std::io::{Error, ErrorKind};
+use std::io::{self, Error, ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Output, Stdio};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 /// Basic structure that details [Haxe](https://haxe.org/) versions.
+///
+/// Versions are ordered by comparing their dot-separated numeric components,
+/// where missing components count as `0`. Following semantic versioning, a
+/// pre-release such as `4.3.0-rc.1` sorts before its final release, and
+/// pre-releases of the same version are ordered by their dot-separated
+/// identifiers, where numeric identifiers are compared numerically and sort
+/// before other ones. The exception is a nightly build named `4.3.0-nightly`,
+/// which sorts after its base version. Build metadata, as in `4.3.0+build`,
+/// is ignored. Versions that compare equally this way are then ordered by
+/// their raw strings.
+///
+/// ```rust
+/// use libmask::*;
+///
+/// assert!(HaxeVersion("4.10.0".into()) > HaxeVersion("4.9.1".into()));
+/// assert!(HaxeVersion("4.3.0-nightly".into()) > HaxeVersion("4.3.0".into()));
+/// assert!(HaxeVersion("4.3.0-nightly".into()) < HaxeVersion("4.3.1".into()));
+/// assert!(HaxeVersion("4.3.0-rc.1".into()) < HaxeVersion("4.3.0".into()));
+///
+/// let mut versions: Vec<HaxeVersion> = [
+///     "4.3.1", "4.3.0+build", "4.3.0-nightly", "4.3.0-rc.1", "4.3.0", "4.3",
+///     "4.3.0-beta.10", "4.3.0-beta", "4.3.1-rc.1", "4.3.0-beta.2",
+///     "4.3.0-alpha.1", "4.2.5", "4.3.0-rc.1+build",
+/// ]
+/// .map(|version| HaxeVersion(version.into()))
+/// .into();
+/// versions.sort();
+/// let names: Vec<&str> = versions.iter().map(|version| version.0.as_str()).collect();
+/// assert_eq!(
+///     names,
+///     [
+///         "4.2.5", "4.3.0-alpha.1", "4.3.0-beta", "4.3.0-beta.2", "4.3.0-beta.10",
+///         "4.3.0-rc.1", "4.3.0-rc.1+build", "4.3", "4.3.0", "4.3.0+build",
+///         "4.3.0-nightly", "4.3.1-rc.1", "4.3.1",
+///     ]
+/// );
+/// ```
 pub struct HaxeVersion(pub String);
 
 impl HaxeVersion {
-    /// Gets the directory where all Haxe versions are stored without performing any checking.
+    /// Gets the directory where all Haxe versions are stored without checking its contents.
     ///
     /// Although this method is not typically used in most operations, it's useful
     /// for simple tasks like listing Haxe versions.
+    ///
+    /// This is the `MASK_HAXE_DIR` environment variable if it's set and not
+    /// empty, and the `.haxe` directory inside of the home directory
+    /// otherwise. An [Error] of kind [ErrorKind::NotFound] is returned if the
+    /// home directory can't be determined, or if it doesn't exist, which is
+    /// common in minimal containers.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// let home = std::env::temp_dir().join("libmask-missing-home-example");
+    /// let versions = std::env::temp_dir().join("libmask-haxe-dir-example");
+    /// # let _ = std::fs::remove_dir_all(&home);
+    /// # let _ = std::fs::remove_dir_all(&versions);
+    /// unsafe {
+    ///     std::env::remove_var("MASK_HAXE_DIR");
+    ///     std::env::set_var("HOME", &home);
+    /// }
+    /// let error = HaxeVersion::get_haxe_installations().unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    /// assert!(error.to_string().contains("MASK_HAXE_DIR"));
+    ///
+    /// unsafe { std::env::set_var("MASK_HAXE_DIR", &versions) };
+    /// assert_eq!(HaxeVersion::get_haxe_installations().unwrap(), versions);
+    ///
+    /// let error = HaxeVersion::list_installed().err().unwrap();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    /// assert!(error.to_string().contains("installations directory"));
+    ///
+    /// unsafe {
+    ///     std::env::remove_var("MASK_HAXE_DIR");
+    ///     std::env::remove_var("HOME");
+    /// }
+    /// match HaxeVersion::get_haxe_installations() {
+    ///     // Without HOME, the home directory can still come from the user database.
+    ///     Ok(path) => assert_eq!(path, std::env::home_dir().unwrap().join(".haxe")),
+    ///     Err(error) => {
+    ///         assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    ///         assert!(error.to_string().starts_with("Home directory not accessible"));
+    ///     }
+    /// }
+    /// ```
     pub fn get_haxe_installations() -> Result<PathBuf, Error> {
+        if let Some(dir) = env::var_os("MASK_HAXE_DIR").filter(|dir| !dir.is_empty()) {
+            return Ok(PathBuf::from(dir));
+        }
         let home: Option<PathBuf> = std::env::home_dir();
-        if let Some(mut buffer) = home {
-            buffer.push(".haxe");
-            return Ok(buffer);
+        match home {
+            Some(buffer) if !buffer.is_dir() => Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "Home directory \"{}\" does not exist; create it, or set MASK_HAXE_DIR \
+                    to the directory containing Haxe versions",
+                    display_path(&buffer)
+                ),
+            )),
+            Some(mut buffer) => {
+                buffer.push(".haxe");
+                Ok(buffer)
+            }
+            None => Err(Error::new(
+                ErrorKind::NotFound,
+                "Home directory not accessible; set HOME, or set MASK_HAXE_DIR \
+                to the directory containing Haxe versions",
+            )),
+        }
+    }
+
+    /// Lists every Haxe version available in the installations directory.
+    ///
+    /// Only directories are considered, and their order is the one reported
+    /// by the filesystem. Directories whose names aren't valid UTF-8 are
+    /// skipped. Use [installed_sorted](#method.installed_sorted) to get the
+    /// versions in a meaningful order.
+    ///
+    /// Aliases created by [link_as](#method.link_as) aren't separate
+    /// installations, so they're left out; see
+    /// [list_aliases](#method.list_aliases) for them.
+    ///
+    /// A missing installations directory produces an [Error] of kind
+    /// [ErrorKind::NotFound] that names it.
+    pub fn list_installed() -> Result<Vec<HaxeVersion>, Error> {
+        Ok(HaxeVersion::read_installations()?
+            .into_iter()
+            .filter(|(_, target)| target.is_none())
+            .map(|(version, _)| version)
+            .collect())
+    }
+
+    /// Lists every alias in the installations directory, alongside the Haxe version it points at.
+    ///
+    /// The order is the one reported by the filesystem.
+    pub fn list_aliases() -> Result<Vec<(HaxeVersion, HaxeVersion)>, Error> {
+        Ok(HaxeVersion::read_installations()?
+            .into_iter()
+            .filter_map(|(alias, target)| target.map(|target| (alias, target)))
+            .collect())
+    }
+
+    /// Reads every directory in the installations directory, alongside the
+    /// version it's an alias of, if any.
+    ///
+    /// An entry is an alias if it's a symlink to another entry of the
+    /// installations directory. Symlinks to anywhere else are treated as
+    /// regular installations.
+    fn read_installations() -> Result<Vec<(HaxeVersion, Option<HaxeVersion>)>, Error> {
+        let mut versions: Vec<(HaxeVersion, Option<HaxeVersion>)> = Vec::new();
+        let installations: PathBuf = HaxeVersion::get_haxe_installations()?;
+        let entries: fs::ReadDir = fs::read_dir(&installations).map_err(|e| {
+            if e.kind() == ErrorKind::NotFound {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "Haxe installations directory \"{}\" does not exist; create it \
+                        and place Haxe versions in it, or set MASK_HAXE_DIR",
+                        display_path(&installations)
+                    ),
+                )
+            } else {
+                e
+            }
+        })?;
+        for entry in entries {
+            let entry: fs::DirEntry = entry?;
+            if !entry.path().is_dir() {
+                continue;
+            }
+            if let Ok(name) = entry.file_name().into_string() {
+                let target: Option<HaxeVersion> = if entry.file_type()?.is_symlink() {
+                    fs::read_link(entry.path())
+                        .ok()
+                        .map(|target| installations.join(target))
+                        .filter(|target| target.parent() == Some(installations.as_path()))
+                        .and_then(|target| {
+                            target
+                                .file_name()
+                                .and_then(OsStr::to_str)
+                                .map(|name| HaxeVersion(name.to_string()))
+                        })
+                } else {
+                    None
+                };
+                versions.push((HaxeVersion(name), target));
+            }
+        }
+        Ok(versions)
+    }
+
+    /// Checks whether the installations directory can be written to.
+    ///
+    /// Operations that change the installations directory call this before
+    /// doing anything, so that a read-only directory, as is common on NixOS
+    /// and locked-down CI machines, is reported with an [Error] of kind
+    /// [ErrorKind::PermissionDenied] that suggests `MASK_HAXE_DIR`, rather
+    /// than a generic error halfway through. Reading from a read-only
+    /// installations directory works as usual.
+    ///
+    /// The directory is considered read-only if its permissions say so, or if
+    /// a probe file can't be created in it.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-read-only-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// std::fs::create_dir_all(root.join("4.3.7").join("std"))?;
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &root);
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    /// }
+    /// assert!(HaxeVersion::check_installations_writable().is_ok());
+    ///
+    /// let mut permissions = std::fs::metadata(&root)?.permissions();
+    /// permissions.set_readonly(true);
+    /// std::fs::set_permissions(&root, permissions.clone())?;
+    ///
+    /// assert_eq!(HaxeVersion::list_installed()?.len(), 1);
+    /// let error = HaxeVersion("4.3.7".into()).link_as("stable").unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+    /// assert!(error.to_string().contains("read-only"));
+    /// # permissions.set_readonly(false);
+    /// # std::fs::set_permissions(&root, permissions)?;
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn check_installations_writable() -> Result<(), Error> {
+        let installations: PathBuf = HaxeVersion::get_haxe_installations()?;
+        let read_only: Error = Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "Installations root is read-only: {}; set MASK_HAXE_DIR to a writable location",
+                display_path(&installations)
+            ),
+        );
+        if fs::metadata(&installations)?.permissions().readonly() {
+            return Err(read_only);
+        }
+
+        let probe: PathBuf =
+            installations.join(format!(".mask-write-probe-{}", std::process::id()));
+        match fs::File::create_new(&probe) {
+            Ok(_) => fs::remove_file(probe),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem
+                ) =>
+            {
+                Err(read_only)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates an alias for this Haxe version in the installations directory.
+    ///
+    /// The alias is a symlink named `name` that points at this version, so
+    /// it can be used anywhere a version can, such as a `stable` alias for
+    /// the version a team currently uses. An existing alias of the same name
+    /// is replaced, but anything else, such as an actual installation, is
+    /// never overwritten and produces an [Error] of kind
+    /// [ErrorKind::AlreadyExists]. The name must be valid according to
+    /// [is_valid_directory_name](#method.is_valid_directory_name), and this
+    /// version must be installed.
+    ///
+    /// On Windows, creating symlinks may require Developer Mode to be enabled.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-link-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// std::fs::create_dir_all(root.join("4.3.7").join("std"))?;
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &root);
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    /// }
+    ///
+    /// let version = HaxeVersion("4.3.7".into());
+    /// version.link_as("stable")?;
+    /// assert_eq!(HaxeVersion::list_installed()?.len(), 1);
+    /// let aliases = HaxeVersion::list_aliases()?;
+    /// assert_eq!(aliases[0].0.0, "stable");
+    /// assert!(aliases[0].1 == version);
+    ///
+    /// let error = HaxeVersion("stable".into()).link_as("4.3.7").unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn link_as(&self, name: &str) -> Result<(), Error> {
+        self.get_path_installed()?;
+        HaxeVersion::check_installations_writable()?;
+        let link: PathBuf = HaxeVersion::get_version(name)?;
+        match fs::symlink_metadata(&link) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let is_alias: bool = HaxeVersion::list_aliases()?
+                    .iter()
+                    .any(|(alias, _)| alias.0 == name);
+                if !is_alias {
+                    return Err(Error::new(
+                        ErrorKind::AlreadyExists,
+                        format!(
+                            "\"{}\" already exists and is not an alias",
+                            display_path(&link)
+                        ),
+                    ));
+                }
+                fs::remove_file(&link).or_else(|_| fs::remove_dir(&link))?;
+            }
+            Ok(_) => {
+                return Err(Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!(
+                        "\"{}\" already exists and is not an alias",
+                        display_path(&link)
+                    ),
+                ));
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(&self.0, &link);
+        #[cfg(windows)]
+        return std::os::windows::fs::symlink_dir(self.get_path()?, &link);
+        #[cfg(not(any(unix, windows)))]
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "Aliases are not supported on this platform, so Haxe version {} cannot be linked",
+                self.0
+            ),
+        ));
+    }
+
+    /// Checks whether the directory of this version is a symlink, without following it.
+    ///
+    /// Aliases created by [link_as](#method.link_as) are symlinks, but so are
+    /// versions linked in from elsewhere by hand, which are still listed as
+    /// installations. A missing version produces an [Error] of kind
+    /// [ErrorKind::NotFound].
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-symlink-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// std::fs::create_dir_all(root.join("4.3.7").join("std"))?;
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &root);
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    /// }
+    ///
+    /// let version = HaxeVersion("4.3.7".into());
+    /// version.link_as("stable")?;
+    /// assert!(!version.is_symlink()?);
+    /// assert!(HaxeVersion("stable".into()).is_symlink()?);
+    /// assert!(HaxeVersion::list_installed()? == [version]);
+    /// assert_eq!(
+    ///     HaxeVersion("missing".into()).is_symlink().unwrap_err().kind(),
+    ///     std::io::ErrorKind::NotFound
+    /// );
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn is_symlink(&self) -> Result<bool, Error> {
+        Ok(fs::symlink_metadata(self.get_path()?)?
+            .file_type()
+            .is_symlink())
+    }
+
+    /// Works the same as [list_installed](#method.list_installed), but sorts the versions from newest to oldest.
+    ///
+    /// Nightly builds sort after their base stable version, so `4.3.0-nightly`
+    /// is listed before `4.3.0`, while other pre-releases like `4.3.0-rc.1`
+    /// are listed after it. The sort is stable.
+    pub fn installed_sorted() -> Result<Vec<HaxeVersion>, Error> {
+        let mut versions: Vec<HaxeVersion> = HaxeVersion::list_installed()?;
+        versions.sort_by(|a, b| b.cmp(a));
+        Ok(versions)
+    }
+
+    /// Finds the oldest installed Haxe version that is newer than this one.
+    ///
+    /// [`None`] is returned if this is already the newest installed version.
+    /// This version doesn't need to be installed itself.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let versions = std::env::temp_dir().join("libmask-next-example");
+    /// # let _ = std::fs::remove_dir_all(&versions);
+    /// for version in ["4.2.5", "4.3.6", "4.3.7"] {
+    ///     std::fs::create_dir_all(versions.join(version))?;
+    /// }
+    /// unsafe { std::env::set_var("MASK_HAXE_DIR", &versions) };
+    ///
+    /// let next = |version: &str| HaxeVersion(version.into()).next_installed();
+    /// assert_eq!(next("4.2.5")?.unwrap().0, "4.3.6");
+    /// assert_eq!(next("4.3.0")?.unwrap().0, "4.3.6");
+    /// assert!(next("4.3.7")?.is_none());
+    /// # std::fs::remove_dir_all(versions)
+    /// # }
+    /// ```
+    pub fn next_installed(&self) -> Result<Option<HaxeVersion>, Error> {
+        Ok(HaxeVersion::list_installed()?
+            .into_iter()
+            .filter(|version| version > self)
+            .min())
+    }
+
+    /// Finds the newest installed Haxe version that is older than this one.
+    ///
+    /// This is the counterpart to [next_installed](#method.next_installed),
+    /// and returns [`None`] if this is already the oldest installed version.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let versions = std::env::temp_dir().join("libmask-previous-example");
+    /// # let _ = std::fs::remove_dir_all(&versions);
+    /// for version in ["4.2.5", "4.3.6", "4.3.7"] {
+    ///     std::fs::create_dir_all(versions.join(version))?;
+    /// }
+    /// unsafe { std::env::set_var("MASK_HAXE_DIR", &versions) };
+    ///
+    /// let previous = |version: &str| HaxeVersion(version.into()).previous_installed();
+    /// assert_eq!(previous("4.3.7")?.unwrap().0, "4.3.6");
+    /// assert_eq!(previous("4.3.0")?.unwrap().0, "4.2.5");
+    /// assert!(previous("4.2.5")?.is_none());
+    /// # std::fs::remove_dir_all(versions)
+    /// # }
+    /// ```
+    pub fn previous_installed(&self) -> Result<Option<HaxeVersion>, Error> {
+        Ok(HaxeVersion::list_installed()?
+            .into_iter()
+            .filter(|version| version < self)
+            .max())
+    }
+
+    /// Finds the newest installed Haxe version that satisfies a version requirement.
+    ///
+    /// See [matches](#method.matches) for the syntax of requirements.
+    /// [`None`] is returned if no installed version satisfies it, so that the
+    /// caller can decide what to do instead.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let home = std::env::temp_dir().join("libmask-matching-example");
+    /// # let _ = std::fs::remove_dir_all(&home);
+    /// for version in ["4.2.5", "4.3.6", "4.3.7", "5.0.0"] {
+    ///     std::fs::create_dir_all(home.join(".haxe").join(version))?;
+    /// }
+    /// unsafe {
+    ///     std::env::remove_var("MASK_HAXE_DIR");
+    ///     std::env::set_var("HOME", &home);
+    /// }
+    ///
+    /// let newest = |req| HaxeVersion::installed_matching(req).map(|v| v.map(|v| v.0));
+    /// assert_eq!(newest("4")?.as_deref(), Some("4.3.7"));
+    /// assert_eq!(newest("<4.3.7")?.as_deref(), Some("4.3.6"));
+    /// assert_eq!(newest("^4.2.5")?.as_deref(), Some("4.3.7"));
+    /// assert_eq!(newest("~4.2")?.as_deref(), Some("4.2.5"));
+    /// assert_eq!(newest("*")?.as_deref(), Some("5.0.0"));
+    /// assert_eq!(newest("3.x")?, None);
+    /// # std::fs::remove_dir_all(home)
+    /// # }
+    /// ```
+    pub fn installed_matching(req: &str) -> Result<Option<HaxeVersion>, Error> {
+        let mut newest: Option<HaxeVersion> = None;
+        for version in HaxeVersion::list_installed()? {
+            if version.matches(req)? && newest.as_ref().is_none_or(|newest| version > *newest) {
+                newest = Some(version);
+            }
         }
-        Err(Error::new(
-            ErrorKind::NotFound,
-            "Home directory not accessible",
-        ))
+        Ok(newest)
+    }
+
+    /// Resolves this version to a concrete installed Haxe version, and explains how.
+    ///
+    /// The following steps are applied repeatedly until an installed version
+    /// is reached:
+    ///
+    /// 1. An alias created by [link_as](#method.link_as) resolves to the
+    ///    version it points at.
+    /// 2. `latest` resolves to the newest installed version.
+    /// 3. A version requirement resolves to the newest installed version that
+    ///    satisfies it, as done by
+    ///    [installed_matching](#method.installed_matching).
+    ///
+    /// The resolved version is returned alongside a description of each step
+    /// that was applied. If no step applies, the last version is returned
+    /// as-is, so a version that isn't installed resolves to itself. A cycle,
+    /// such as two aliases pointing at each other, produces an [Error] of
+    /// kind [ErrorKind::InvalidData] that contains the steps taken.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-resolve-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// for version in ["4.2.5", "4.3.7"] {
+    ///     std::fs::create_dir_all(root.join(version).join("std"))?;
+    /// }
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &root);
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    /// }
+    /// HaxeVersion("4.2.5".into()).link_as("stable")?;
+    ///
+    /// let (version, steps) = HaxeVersion("stable".into()).resolve_chain()?;
+    /// assert_eq!(version.0, "4.2.5");
+    /// assert_eq!(steps, ["alias stable -> 4.2.5", "installed 4.2.5"]);
+    ///
+    /// let (version, steps) = HaxeVersion("latest".into()).resolve_chain()?;
+    /// assert_eq!(version.0, "4.3.7");
+    /// assert_eq!(steps.len(), 2);
+    ///
+    /// let (version, _) = HaxeVersion("^4.2".into()).resolve_chain()?;
+    /// assert_eq!(version.0, "4.3.7");
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn resolve_chain(&self) -> Result<(HaxeVersion, Vec<String>), Error> {
+        let mut version: HaxeVersion = self.clone();
+        let mut seen: Vec<HaxeVersion> = vec![version.clone()];
+        let mut steps: Vec<String> = Vec::new();
+
+        loop {
+            if HaxeVersion::list_installed()?.contains(&version) {
+                steps.push(format!("installed {}", version.0));
+                return Ok((version, steps));
+            }
+            let next: Option<(HaxeVersion, &str)> = match version.resolve_alias()? {
+                Some(target) => Some((target, "alias")),
+                None => match version.resolve_latest()? {
+                    Some(newest) => Some((newest, "latest")),
+                    None => version.resolve_range()?.map(|newest| (newest, "range")),
+                },
+            };
+
+            match next {
+                Some((next, step)) => {
+                    steps.push(format!("{} {} -> {}", step, version.0, next.0));
+                    if seen.contains(&next) {
+                        steps.push(format!("cycle detected at {}", next.0));
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!(
+                                "Resolving Haxe version {} is cyclic: {}",
+                                self.0,
+                                steps.join(", ")
+                            ),
+                        ));
+                    }
+                    seen.push(next.clone());
+                    version = next;
+                }
+                None => return Ok((version, steps)),
+            }
+        }
+    }
+
+    /// Resolves an alias to the version it points at.
+    fn resolve_alias(&self) -> Result<Option<HaxeVersion>, Error> {
+        Ok(HaxeVersion::list_aliases()?
+            .into_iter()
+            .find(|(alias, _)| alias == self)
+            .map(|(_, target)| target))
+    }
+
+    /// Resolves `latest` to the newest installed version.
+    fn resolve_latest(&self) -> Result<Option<HaxeVersion>, Error> {
+        if self.0 != "latest" {
+            return Ok(None);
+        }
+        Ok(HaxeVersion::installed_sorted()?.into_iter().next())
+    }
+
+    /// Resolves a version requirement to the newest installed version that satisfies it.
+    ///
+    /// Versions that aren't valid requirements resolve to nothing.
+    fn resolve_range(&self) -> Result<Option<HaxeVersion>, Error> {
+        match HaxeVersion::installed_matching(&self.0) {
+            Err(e) if e.kind() == ErrorKind::InvalidInput => Ok(None),
+            result => result,
+        }
+    }
+
+    /// Checks whether this version satisfies a version requirement.
+    ///
+    /// A requirement is made of one or more comma-separated comparators, all
+    /// of which have to be satisfied. Each comparator is a version, optionally
+    /// preceded by an operator:
+    ///
+    /// - No operator or `=` matches versions starting with the given
+    ///   components, so `4.3` and `4.3.x` both match `4.3.7`. Versions with
+    ///   a suffix, like nightly builds, are only matched if the requirement
+    ///   has the same suffix, except by `*`, which matches any version.
+    /// - `>`, `>=`, `<`, and `<=` compare versions using their ordering.
+    /// - `^` matches versions that are compatible according to semantic
+    ///   versioning, so `^4.2` matches `4.3.7` but not `5.0.0`.
+    /// - `~` matches versions with the same minor version, so `~4.3.1`
+    ///   matches `4.3.7` but not `4.4.0`.
+    ///
+    /// Neither `^` nor `~` match pre-releases of the version they stop at,
+    /// so `^4.2` doesn't match `5.0.0-rc.1`, even though it sorts before
+    /// `5.0.0`.
+    ///
+    /// An [ErrorKind::InvalidInput] error is returned if the requirement
+    /// can't be parsed.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let version = HaxeVersion("4.3.7".into());
+    /// assert!(version.matches("4.3")?);
+    /// assert!(version.matches("4.x")?);
+    /// assert!(version.matches(">=4.2, <5")?);
+    /// assert!(version.matches("^4.2.5")?);
+    /// assert!(version.matches("~4.3.1")?);
+    /// assert!(!version.matches("~4.2")?);
+    /// assert!(!HaxeVersion("4.3.7-nightly".into()).matches("4.3")?);
+    /// assert!(HaxeVersion("4.3.7-nightly".into()).matches("*")?);
+    /// assert!(!HaxeVersion("5.0.0-rc.1".into()).matches("^4.2")?);
+    /// assert!(HaxeVersion("4.4.0-rc.1".into()).matches("^4.2")?);
+    /// assert!(version.matches(">=four").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn matches(&self, req: &str) -> Result<bool, Error> {
+        for comparator in req.split(',') {
+            if !self.matches_comparator(comparator.trim())? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Checks whether the name of this version matches a pattern, as used to filter listings.
+    ///
+    /// A pattern containing `*` or `?` is a glob that has to match the whole
+    /// name, where `*` matches any number of characters and `?` matches
+    /// exactly one. Any other pattern matches names that contain it. Unlike
+    /// [matches](#method.matches), this only looks at the name, so it works
+    /// for any directory name, including nightly builds and aliases.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// let version = HaxeVersion("4.3.7-nightly".into());
+    /// assert!(version.name_matches("nightly"));
+    /// assert!(version.name_matches("4.3.*"));
+    /// assert!(version.name_matches("4.?.7*"));
+    /// assert!(!version.name_matches("4.3.?"));
+    /// assert!(!version.name_matches("4.2"));
+    /// assert!(HaxeVersion("4.2.5".into()).name_matches(""));
+    /// ```
+    pub fn name_matches(&self, pattern: &str) -> bool {
+        if !pattern.contains(['*', '?']) {
+            return self.0.contains(pattern);
+        }
+
+        let name: Vec<char> = self.0.chars().collect();
+        let pattern: Vec<char> = pattern.chars().collect();
+        let (mut n, mut p): (usize, usize) = (0, 0);
+        // The position of the last `*`, and of the name when it was reached.
+        let mut star: Option<(usize, usize)> = None;
+        while n < name.len() {
+            match pattern.get(p) {
+                Some('*') => {
+                    star = Some((p, n));
+                    p += 1;
+                }
+                Some(&c) if c == '?' || c == name[n] => {
+                    n += 1;
+                    p += 1;
+                }
+                _ => match star {
+                    // Let the last `*` consume one more character, and retry.
+                    Some((star_p, star_n)) => {
+                        star = Some((star_p, star_n + 1));
+                        p = star_p + 1;
+                        n = star_n + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        pattern[p..].iter().all(|&c| c == '*')
+    }
+
+    /// Checks whether this version satisfies a single comparator of a version requirement.
+    fn matches_comparator(&self, comparator: &str) -> Result<bool, Error> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid version requirement \"{}\"", comparator),
+            )
+        };
+        let (op, target) = [">=", "<=", ">", "<", "=", "^", "~"]
+            .iter()
+            .find_map(|op| comparator.strip_prefix(op).map(|rest| (*op, rest.trim())))
+            .unwrap_or(("", comparator));
+        if target.is_empty() {
+            return Err(invalid());
+        }
+
+        let (base, suffix) = target.split_at(target.find(['-', '+']).unwrap_or(target.len()));
+        let mut fixed: Vec<u64> = Vec::new();
+        for part in base.split('.') {
+            match part {
+                "x" | "X" | "*" => break,
+                part => fixed.push(part.parse().map_err(|_| invalid())?),
+            }
+        }
+        let join = |parts: &[u64]| -> HaxeVersion {
+            let parts: Vec<String> = parts.iter().map(u64::to_string).collect();
+            HaxeVersion(if parts.is_empty() {
+                "0".to_string()
+            } else {
+                parts.join(".")
+            })
+        };
+        let lower: HaxeVersion = HaxeVersion(format!("{}{}", join(&fixed).0, suffix));
+        let bump = |index: usize| -> HaxeVersion {
+            let mut upper: Vec<u64> = fixed[..=index].to_vec();
+            upper[index] += 1;
+            join(&upper)
+        };
+        // Pre-releases of the upper bound sort before it, but belong to it.
+        let below =
+            |upper: HaxeVersion| -> bool { HaxeVersion(self.parse_semver().0.join(".")) < upper };
+
+        Ok(match op {
+            ">=" => *self >= lower,
+            "<=" => *self <= lower,
+            ">" => *self > lower,
+            "<" => *self < lower,
+            "=" | "" if fixed.is_empty() && suffix.is_empty() => true,
+            "^" | "~" if fixed.is_empty() => true,
+            "^" => {
+                let index: usize = fixed
+                    .iter()
+                    .position(|part| *part != 0)
+                    .unwrap_or(fixed.len() - 1);
+                *self >= lower && below(bump(index))
+            }
+            "~" => *self >= lower && below(bump(fixed.len().min(2) - 1)),
+            _ => {
+                let (own_base, own_suffix) = self
+                    .0
+                    .split_at(self.0.find(['-', '+']).unwrap_or(self.0.len()));
+                let own: Vec<Option<u64>> =
+                    own_base.split('.').map(|part| part.parse().ok()).collect();
+                own_suffix == suffix
+                    && fixed.iter().enumerate().all(|(index, part)| {
+                        own.get(index).copied().unwrap_or(Some(0)) == Some(*part)
+                    })
+            }
+        })
+    }
+
+    /// Splits this version into its dot-separated components, its
+    /// pre-release, and its build metadata, following semantic versioning.
+    ///
+    /// The build metadata follows the first `+`, and the pre-release follows
+    /// the first `-` before it. Either is empty if the version doesn't have
+    /// one.
+    fn parse_semver(&self) -> (Vec<&str>, &str, &str) {
+        let (rest, build) = match self.0.split_once('+') {
+            Some((rest, build)) => (rest, build),
+            None => (self.0.as_str(), ""),
+        };
+        let (base, pre) = rest.split_once('-').unwrap_or((rest, ""));
+        (base.split('.').collect(), pre, build)
+    }
+
+    /// Checks whether a version string can safely name a directory inside the
+    /// installations directory.
+    ///
+    /// Version strings are joined onto the installations directory, so any
+    /// string that could point outside of it, or at something other than a
+    /// single entry inside it, is rejected. This covers:
+    ///
+    /// - empty strings;
+    /// - `.` and `..`, which refer to the installations directory itself and
+    ///   its parent;
+    /// - strings containing `/` or `\`, which would add path components on
+    ///   any platform;
+    /// - strings containing control characters, including the null byte, which
+    ///   cannot appear in file names on most platforms;
+    /// - strings with leading or trailing whitespace, which usually come from
+    ///   badly edited files and are easy to confuse with another version.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// assert!(HaxeVersion::is_valid_directory_name("4.3.7"));
+    /// assert!(HaxeVersion::is_valid_directory_name("4.3.0-rc.1+nightly"));
+    /// assert!(HaxeVersion::is_valid_directory_name("..."));
+    /// assert!(!HaxeVersion::is_valid_directory_name(""));
+    /// assert!(!HaxeVersion::is_valid_directory_name("."));
+    /// assert!(!HaxeVersion::is_valid_directory_name(".."));
+    /// assert!(!HaxeVersion::is_valid_directory_name("../4.3.7"));
+    /// assert!(!HaxeVersion::is_valid_directory_name("4.3\\7"));
+    /// assert!(!HaxeVersion::is_valid_directory_name("4.3.7\0"));
+    /// assert!(!HaxeVersion::is_valid_directory_name(" 4.3.7"));
+    /// assert!(!HaxeVersion::is_valid_directory_name("4.3.7 "));
+    /// ```
+    pub fn is_valid_directory_name(name: &str) -> bool {
+        !name.is_empty()
+            && name != "."
+            && name != ".."
+            && name.trim() == name
+            && !name
+                .chars()
+                .any(|c| c == '/' || c == '\\' || c.is_control())
     }
 
     /// Checks if a Haxe version exists, and returns its path.
     ///
     /// This is used internally by `libmask` for methods that cannot use `self`.
+    ///
+    /// Version strings that aren't valid directory names, as described by
+    /// [is_valid_directory_name](#method.is_valid_directory_name), produce an
+    /// [Error] of kind [ErrorKind::InvalidInput].
     pub fn get_version(path: &str) -> Result<PathBuf, Error> {
+        if !HaxeVersion::is_valid_directory_name(path) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "\"{}\" is not a valid Haxe version name",
+                    path.escape_debug()
+                ),
+            ));
+        }
         let mut buffer: PathBuf = HaxeVersion::get_haxe_installations()?;
         buffer.push(path);
         Ok(buffer)
@@ -128,42 +964,1114 @@ impl HaxeVersion {
         HaxeVersion::get_version(&self.0)
     }
 
+    /// Formats the path of this version for messages shown to the user.
+    ///
+    /// This is the path returned by [get_path](#method.get_path), formatted
+    /// by [display_path]. If the path can't be determined, the version itself
+    /// is returned.
+    pub fn disk_path_display(&self) -> String {
+        match self.get_path() {
+            Ok(path) => display_path(&path),
+            Err(_) => self.0.clone(),
+        }
+    }
+
     /// Works the same as [get_path](#method.get_path), but returns the path to the standard library.
+    ///
+    /// The standard library is expected to be in the `std` subdirectory of the
+    /// version directory. Distributions that place it elsewhere can be
+    /// accommodated by setting the `MASK_STD_SUBDIR` environment variable to
+    /// a different path relative to the version directory, such as
+    /// `lib/haxe/std`. The default is used if the variable is unset or empty.
+    ///
+    /// If the version directory exists but its standard library doesn't, as
+    /// with distributions that ship the standard library separately, the
+    /// first directory listed in the `HAXE_STD_PATH` environment variable is
+    /// used instead. Like Haxe itself, the variable is read as a list
+    /// separated the same way as `PATH`. Directories inside the installations
+    /// directory are skipped, since programs run through [ExecBuilder]
+    /// receive the standard library of their own version in that variable.
+    /// Use [locate_std_path](#method.locate_std_path) to find out which one
+    /// was used.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    /// use std::path::Path;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-std-subdir-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &root);
+    ///     std::env::set_var("MASK_STD_SUBDIR", "lib/haxe/std");
+    ///     std::env::remove_var("HAXE_STD_PATH");
+    /// }
+    /// let path = HaxeVersion("4.3.7".into()).get_std_path()?;
+    /// assert_eq!(path, root.join("4.3.7").join(Path::new("lib/haxe/std")));
+    /// # unsafe { std::env::remove_var("MASK_STD_SUBDIR") };
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn get_std_path(&self) -> Result<PathBuf, Error> {
-        let mut buf: PathBuf = self.get_path()?;
-        buf.push("std");
-        Ok(buf)
+        Ok(self.locate_std_path()?.0)
+    }
+
+    /// Works the same as [get_std_path](#method.get_std_path), but also reports where the standard library was found.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-system-std-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// let versions = root.join("versions");
+    /// let system = root.join("system-std");
+    /// std::fs::create_dir_all(versions.join("4.3.7"))?;
+    /// std::fs::create_dir_all(&system)?;
+    /// let search = std::env::join_paths([root.join("missing"), system.clone()]).unwrap();
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &versions);
+    ///     std::env::set_var("HAXE_STD_PATH", &search);
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    /// }
+    ///
+    /// let version = HaxeVersion("4.3.7".into());
+    /// assert_eq!(version.locate_std_path()?, (system.clone(), StdSource::Environment));
+    /// assert!(version.get_path_installed().is_ok());
+    ///
+    /// // The standard library of another installed version isn't a system-wide one.
+    /// std::fs::create_dir_all(versions.join("4.3.6").join("std"))?;
+    /// unsafe { std::env::set_var("HAXE_STD_PATH", versions.join("4.3.6").join("std")) };
+    /// assert_eq!(version.locate_std_path()?.1, StdSource::Bundled);
+    /// assert!(version.get_path_installed().is_err());
+    ///
+    /// std::fs::create_dir(versions.join("4.3.7").join("std"))?;
+    /// assert_eq!(version.locate_std_path()?.1, StdSource::Bundled);
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn locate_std_path(&self) -> Result<(PathBuf, StdSource), Error> {
+        let path: PathBuf = self.get_path()?;
+        let bundled: PathBuf = match env::var("MASK_STD_SUBDIR") {
+            Ok(subdir) if !subdir.is_empty() => path.join(subdir),
+            _ => path.join("std"),
+        };
+        if !bundled.try_exists()?
+            && path.is_dir()
+            && let Some(system) = HaxeVersion::system_std_path()?
+        {
+            return Ok((system, StdSource::Environment));
+        }
+        Ok((bundled, StdSource::Bundled))
+    }
+
+    /// Finds the first directory listed in `HAXE_STD_PATH` that is outside of the installations directory.
+    fn system_std_path() -> Result<Option<PathBuf>, Error> {
+        let Some(search) = env::var_os("HAXE_STD_PATH") else {
+            return Ok(None);
+        };
+        let root: PathBuf = HaxeVersion::get_haxe_installations()?;
+        let root: PathBuf = fs::canonicalize(&root).unwrap_or(root);
+        Ok(env::split_paths(&search)
+            .filter(|entry| !entry.as_os_str().is_empty() && entry.is_dir())
+            .find(|entry| !fs::canonicalize(entry).is_ok_and(|entry| entry.starts_with(&root))))
+    }
+
+    /// Checks whether a Haxe version is completely installed.
+    ///
+    /// On top of the checks performed by
+    /// [get_path_installed](#method.get_path_installed), this also requires
+    /// the compiler to be present in the version directory. A version that
+    /// isn't complete returns `false` rather than an [Error]; errors are only
+    /// returned if the installation couldn't be inspected at all.
+    pub fn is_complete(&self) -> Result<bool, Error> {
+        match self.get_path_installed() {
+            Ok(path) => path.join(executable_name("haxe")).try_exists(),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Locates the Neko binary bundled with this version, if it ships one.
+    ///
+    /// Bundled Neko builds live in the `neko` subdirectory of the version
+    /// directory, which is also the one exported as `NEKOPATH` by
+    /// [env_vars](#method.env_vars). The name of the binary goes through
+    /// [executable_name], so `neko.exe` is looked for on Windows.
+    ///
+    /// A version without a bundled Neko is a normal case, so it produces
+    /// `Ok(None)`; an [Error] is only returned if the version itself isn't
+    /// installed.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-neko-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// std::fs::create_dir_all(root.join("4.3.7").join("std"))?;
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &root);
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    /// }
+    ///
+    /// let version = HaxeVersion("4.3.7".into());
+    /// assert_eq!(version.neko_path()?, None);
+    ///
+    /// let neko = root.join("4.3.7").join("neko").join(executable_name("neko"));
+    /// std::fs::create_dir_all(neko.parent().unwrap())?;
+    /// std::fs::write(&neko, "")?;
+    /// assert_eq!(version.neko_path()?, Some(neko));
+    /// assert!(HaxeVersion("missing".into()).neko_path().is_err());
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn neko_path(&self) -> Result<Option<PathBuf>, Error> {
+        let neko: PathBuf = self
+            .get_path_installed()?
+            .join("neko")
+            .join(executable_name("neko"));
+        Ok(Some(neko).filter(|neko| neko.is_file()))
+    }
+
+    /// Computes the SHA-256 checksum of the Haxe compiler, as a lowercase hexadecimal string.
+    ///
+    /// Comparing this against a previously recorded checksum detects a
+    /// compiler that was corrupted or swapped out. The compiler is hashed
+    /// as it's read, so it's never fully loaded into memory.
+    pub fn checksum_binary(&self) -> Result<String, Error> {
+        let mut file: fs::File =
+            fs::File::open(self.get_path_installed()?.join(executable_name("haxe")))?;
+        let mut hasher: Sha256 = Sha256::new();
+        io::copy(&mut file, &mut hasher)?;
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+
+    /// Checks whether the Haxe compiler reports the same version as the name of its directory.
+    ///
+    /// The compiler is run with `--version`, and its output is compared to
+    /// the name of the version directory. Build metadata, such as the commit
+    /// hash that follows a `+`, and a leading `v` are ignored on both sides.
+    /// A mismatch usually means that the wrong build was extracted into the
+    /// directory.
+    ///
+    /// The compiler is run the same way as by [ExecBuilder], so it goes
+    /// through the wrapper of `MASK_EXEC_WRAPPER` if one is set.
+    ///
+    /// `Ok(false)` is only returned for a mismatch. A compiler that can't be
+    /// run produces the [Error] reported when starting it, and a compiler that
+    /// exits unsuccessfully produces an [Error] of kind [ErrorKind::Other].
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-reported-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// std::fs::create_dir_all(root.join("4.3.7").join("std"))?;
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &root);
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    ///     std::env::remove_var("MASK_EXEC_WRAPPER");
+    /// }
+    ///
+    /// let error = HaxeVersion("4.3.7".into()).validate_against_reported().unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    ///
+    /// #[cfg(unix)]
+    /// {
+    ///     use std::os::unix::fs::PermissionsExt;
+    ///     let compiler = |version: &str, reported: &str, mode: u32| {
+    ///         let path = root.join(version).join("haxe");
+    ///         std::fs::create_dir_all(root.join(version).join("std"))?;
+    ///         std::fs::write(&path, format!("#!/bin/sh\necho {}\n", reported))?;
+    ///         std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+    ///     };
+    ///     compiler("4.3.7", "4.3.7+3d8d3e4", 0o755)?;
+    ///     assert!(HaxeVersion("4.3.7".into()).validate_against_reported()?);
+    ///     compiler("4.3.6", "4.3.7", 0o755)?;
+    ///     assert!(!HaxeVersion("4.3.6".into()).validate_against_reported()?);
+    ///
+    ///     // A compiler that only runs through the wrapper is validated through it.
+    ///     compiler("4.2.5", "4.2.5", 0o644)?;
+    ///     assert!(HaxeVersion("4.2.5".into()).validate_against_reported().is_err());
+    ///     unsafe { std::env::set_var("MASK_EXEC_WRAPPER", "sh") };
+    ///     assert!(HaxeVersion("4.2.5".into()).validate_against_reported()?);
+    ///     unsafe { std::env::remove_var("MASK_EXEC_WRAPPER") };
+    /// }
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn validate_against_reported(&self) -> Result<bool, Error> {
+        /// Strips the parts of a version that don't identify a release.
+        fn normalize(version: &str) -> &str {
+            let version: &str = version.trim();
+            let version: &str = version.strip_prefix('v').unwrap_or(version);
+            version
+                .split(|c: char| c == '+' || c.is_whitespace())
+                .next()
+                .unwrap_or_default()
+        }
+
+        let output: Output = ExecBuilder::new(Config(self.clone()))
+            .prog("haxe")
+            .args(vec!["--version".to_string()])
+            .build()?
+            .output()?;
+        if !output.status.success() {
+            return Err(Error::other(format!(
+                "The compiler of Haxe version {} failed to report its version",
+                self.0
+            )));
+        }
+
+        // Older compilers print their version to the standard error instead.
+        let reported: String = if output.stdout.iter().all(u8::is_ascii_whitespace) {
+            String::from_utf8_lossy(&output.stderr).into_owned()
+        } else {
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        };
+        Ok(normalize(&reported) == normalize(&self.0))
+    }
+
+    /// Packages the version directory into a gzip-compressed tarball.
+    ///
+    /// Every entry in the tarball is placed under a directory named after the
+    /// version, so extracting it into the installations directory restores
+    /// the version as-is. Files are streamed into the tarball rather than
+    /// being buffered, and symbolic links are stored as links. The version
+    /// has to be installed, and an existing file at the destination is never
+    /// overwritten.
+    pub fn pack(&self, dest: &Path) -> Result<(), Error> {
+        let path: PathBuf = self.get_path_installed()?;
+        let file: fs::File = fs::File::create_new(dest).map_err(|e| {
+            if e.kind() == ErrorKind::AlreadyExists {
+                Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("File \"{}\" already exists", display_path(dest)),
+                )
+            } else {
+                e
+            }
+        })?;
+        let mut builder: tar::Builder<GzEncoder<fs::File>> =
+            tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        builder.follow_symlinks(false);
+        let result: Result<(), Error> = builder
+            .append_dir_all(&self.0, path)
+            .and_then(|_| builder.into_inner())
+            .and_then(|encoder| encoder.finish())
+            .map(|_| ());
+        if result.is_err() {
+            let _ = fs::remove_file(dest);
+        }
+        result
+    }
+
+    /// Copies the version directory to a new version in the installations directory.
+    ///
+    /// This is useful for experimenting with a patched copy of a version
+    /// without touching the original. The new name is validated the same way
+    /// as in [get_version](#method.get_version), and an existing entry with
+    /// that name is never overwritten; an [Error] of kind
+    /// [ErrorKind::AlreadyExists] is returned instead. Files are streamed
+    /// rather than buffered, their permissions, including executable bits, are
+    /// kept, and symbolic links inside the version are copied as links on
+    /// Unix. If copying fails partway, the incomplete copy is removed.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-copy-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// std::fs::create_dir_all(root.join("4.3.7").join("std"))?;
+    /// std::fs::write(root.join("4.3.7").join("std").join("StdTypes.hx"), "// std")?;
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", &root);
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    /// }
+    ///
+    /// let copy = HaxeVersion("4.3.7".into()).copy_to("4.3.7-patched")?;
+    /// assert_eq!(copy.0, "4.3.7-patched");
+    /// let file = root.join("4.3.7-patched").join("std").join("StdTypes.hx");
+    /// assert_eq!(std::fs::read_to_string(file)?, "// std");
+    ///
+    /// let error = HaxeVersion("4.3.7".into()).copy_to("4.3.7-patched").err().unwrap();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+    /// let error = HaxeVersion("4.3.7".into()).copy_to("../escape").err().unwrap();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn copy_to(&self, name: &str) -> Result<HaxeVersion, Error> {
+        let source: PathBuf = self.get_path_installed()?;
+        HaxeVersion::check_installations_writable()?;
+        let dest: PathBuf = HaxeVersion::get_version(name)?;
+        fs::create_dir(&dest).map_err(|e| {
+            if e.kind() == ErrorKind::AlreadyExists {
+                Error::new(
+                    ErrorKind::AlreadyExists,
+                    format!("\"{}\" already exists", display_path(&dest)),
+                )
+            } else {
+                e
+            }
+        })?;
+        match copy_dir_contents(&source, &dest) {
+            Ok(_) => Ok(HaxeVersion(name.to_string())),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dest);
+                Err(e)
+            }
+        }
+    }
+
+    /// Computes the environment variables that programs of this version need.
+    ///
+    /// This returns the `PATH` with the version directory prepended to the
+    /// current one, `HAXE_STD_PATH` pointing at the standard library, and
+    /// `NEKOPATH` if the version directory contains a bundled `neko`
+    /// directory. Both [create_patched_cmd] and `mask-hx env` use this, so
+    /// that they always agree on the environment of a version.
+    ///
+    /// Apart from `PATH`, and `HAXE_STD_PATH` when it's used as described in
+    /// [get_std_path](#method.get_std_path), the environment of the current
+    /// process isn't read.
+    pub fn env_vars(&self) -> Result<Vec<(String, OsString)>, Error> {
+        self.env_vars_with_path(&env::var_os("PATH").unwrap_or_default())
+    }
+
+    /// Works the same as [env_vars](#method.env_vars), but prepends the version directory to a specified `PATH`.
+    fn env_vars_with_path(&self, base: &OsStr) -> Result<Vec<(String, OsString)>, Error> {
+        let path: PathBuf = self.get_path()?;
+        let mut vars: Vec<(String, OsString)> = vec![
+            ("PATH".to_string(), prepend_path(&path, base)),
+            (
+                "HAXE_STD_PATH".to_string(),
+                self.get_std_path()?.into_os_string(),
+            ),
+        ];
+        let neko: PathBuf = path.join("neko");
+        if neko.is_dir() {
+            vars.push(("NEKOPATH".to_string(), neko.into_os_string()));
+        }
+        Ok(vars)
+    }
+
+    /// Checks if a Haxe version is properly installed, and returns its path if it is.
+    ///
+    /// This works the same as [get_path](#method.get_path), but checks for the
+    /// existence of both the Haxe version and its standard library before
+    /// proceeding to return the path.
+    pub fn get_path_installed(&self) -> Result<PathBuf, Error> {
+        if self.get_std_path()?.try_exists()? {
+            Ok(self.get_path()?)
+        } else {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "Haxe version {} could not be found using the standard library",
+                    self.0
+                ),
+            ))
+        }
+    }
+}
+
+impl Ord for HaxeVersion {
+    fn cmp(&self, other: &HaxeVersion) -> Ordering {
+        /// Compares two components or identifiers, numerically if both are numbers.
+        fn compare(a: &str, b: &str) -> Ordering {
+            match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            }
+        }
+
+        /// Ranks a pre-release against the final release it belongs to.
+        fn rank(pre: &str) -> u8 {
+            match pre {
+                "" => 1,
+                pre if pre.split('.').next() == Some("nightly") => 2,
+                _ => 0,
+            }
+        }
+
+        let (base, pre, _) = self.parse_semver();
+        let (other_base, other_pre, _) = other.parse_semver();
+
+        for i in 0..base.len().max(other_base.len()) {
+            let a: &str = base.get(i).copied().unwrap_or("0");
+            let b: &str = other_base.get(i).copied().unwrap_or("0");
+            let ordering: Ordering = compare(a, b);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+
+        let mut identifiers = pre.split('.');
+        let mut other_identifiers = other_pre.split('.');
+        rank(pre)
+            .cmp(&rank(other_pre))
+            .then_with(|| {
+                loop {
+                    // A pre-release that runs out of identifiers first sorts first.
+                    match (identifiers.next(), other_identifiers.next()) {
+                        (Some(a), Some(b)) => match compare(a, b) {
+                            Ordering::Equal => continue,
+                            ordering => break ordering,
+                        },
+                        (a, b) => break a.is_some().cmp(&b.is_some()),
+                    }
+                }
+            })
+            .then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for HaxeVersion {
+    fn partial_cmp(&self, other: &HaxeVersion) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Where the standard library of a Haxe version was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StdSource {
+    /// The standard library is inside of the version directory.
+    Bundled,
+    /// The version directory has no standard library, so the one in
+    /// `HAXE_STD_PATH` is used.
+    Environment,
+}
+
+#[derive(Clone)]
+/// A basic representation of a `libmask` configuration.
+pub struct Config(pub HaxeVersion);
+
+/// The name of the configuration file used when no path is specified.
+pub const DEFAULT_CONFIG: &str = ".mask";
+
+/// The name of a configuration file that overrides [DEFAULT_CONFIG] for an individual developer.
+///
+/// Following the `.env` and `.env.local` convention, this file is meant to be
+/// excluded from version control, so that a developer can use a different
+/// Haxe version without changing the one shared with everyone else.
+pub const LOCAL_CONFIG: &str = ".mask.local";
+
+/// The environment variables that can specify a Haxe version, in order of precedence.
+pub const VERSION_VARS: [&str; 2] = ["MASK_VERSION", "HAXE_VERSION"];
+
+/// The version a configuration file can contain to use the version of the directories above it.
+///
+/// Such a file is skipped by [`Config::from_ancestors`], as if it didn't
+/// exist. This lets a project inside a monorepo keep a configuration file
+/// without pinning a version of its own.
+pub const INHERIT_VERSION: &str = "inherit";
+
+impl Config {
+    /// This reads a sample configuration from the disk, and returns it if it's valid as a [Result].
+    ///
+    /// If no path is specified, the path returned by
+    /// [default_path](#method.default_path) is used. If the path is a
+    /// directory, the configuration inside of it is read instead, which is
+    /// [LOCAL_CONFIG] if it exists there, and [DEFAULT_CONFIG] otherwise.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let dir = std::env::temp_dir().join("libmask-directory-example");
+    /// # let _ = std::fs::remove_dir_all(&dir);
+    /// Config::write(dir.join(DEFAULT_CONFIG).to_str(), "4.3.7")?;
+    ///
+    /// assert_eq!(Config::new(dir.to_str())?.0.0, "4.3.7");
+    /// assert_eq!(Config::new(dir.join(DEFAULT_CONFIG).to_str())?.0.0, "4.3.7");
+    /// # std::fs::remove_dir_all(dir)
+    /// # }
+    /// ```
+    ///
+    /// The kind of the [Error] tells a missing configuration apart from one
+    /// that exists but can't be used, so that callers can fall back to
+    /// something else only in the former case:
+    ///
+    /// - [ErrorKind::NotFound] if the file doesn't exist.
+    /// - [ErrorKind::PermissionDenied] if the file can't be opened.
+    /// - [ErrorKind::InvalidData] if the file isn't valid UTF-8, or contains
+    ///   conflicting versions.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    /// use std::io::ErrorKind;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let dir = std::env::temp_dir().join("libmask-unreadable-example");
+    /// # let _ = std::fs::remove_dir_all(&dir);
+    /// std::fs::create_dir_all(&dir)?;
+    /// let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    ///
+    /// let error = Config::new(Some(&path("missing.mask"))).err().unwrap();
+    /// assert_eq!(error.kind(), ErrorKind::NotFound);
+    ///
+    /// std::fs::write(path("binary.mask"), [0xff, 0xfe, 0x00])?;
+    /// let error = Config::new(Some(&path("binary.mask"))).err().unwrap();
+    /// assert_eq!(error.kind(), ErrorKind::InvalidData);
+    ///
+    /// #[cfg(unix)]
+    /// {
+    ///     use std::os::unix::fs::PermissionsExt;
+    ///     std::fs::write(path("locked.mask"), "4.3.7")?;
+    ///     let permissions = std::fs::Permissions::from_mode(0o000);
+    ///     std::fs::set_permissions(path("locked.mask"), permissions)?;
+    ///     // Privileged users can read the file regardless of its permissions.
+    ///     if std::fs::read(path("locked.mask")).is_err() {
+    ///         let error = Config::new(Some(&path("locked.mask"))).err().unwrap();
+    ///         assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+    ///     }
+    /// }
+    /// # std::fs::remove_dir_all(dir)
+    /// # }
+    /// ```
+    pub fn new(path: Option<&str>) -> Result<Config, Error> {
+        let version: String = Config::read_from_file(&Config::reading_path(path))?;
+        Ok(Config(HaxeVersion(version)))
+    }
+
+    /// Gets the file that [new](#method.new) reads for a path.
+    fn reading_path(path: Option<&str>) -> String {
+        let path: PathBuf = match path {
+            Some(dir) if Path::new(dir).join(LOCAL_CONFIG).is_file() => {
+                Config::canonical_config_path(Some(
+                    &Path::new(dir).join(LOCAL_CONFIG).to_string_lossy(),
+                ))
+            }
+            Some(path) => Config::canonical_config_path(Some(path)),
+            None => Config::canonical_config_path(Some(Config::default_path())),
+        };
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Normalizes a configuration path, so that every way of referring to a file results in the same path.
+    ///
+    /// The path defaults to [DEFAULT_CONFIG], and a directory is replaced by
+    /// [DEFAULT_CONFIG] inside of it. Relative paths are made absolute using
+    /// the working directory, and `.` components are removed, so `.mask` and
+    /// `./.mask` both result in the same path. This is used by both
+    /// [new](#method.new) and [write](#method.write), so that reading and
+    /// writing the same path always target the same file.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let dir = std::env::temp_dir().join("libmask-canonical-example");
+    /// # let _ = std::fs::remove_dir_all(&dir);
+    /// std::fs::create_dir_all(&dir)?;
+    /// std::env::set_current_dir(&dir)?;
+    ///
+    /// let canonical = Config::canonical_config_path(None);
+    /// assert_eq!(canonical, dir.join(DEFAULT_CONFIG));
+    /// assert_eq!(Config::canonical_config_path(Some("./.mask")), canonical);
+    /// assert_eq!(Config::canonical_config_path(Some(".")), canonical);
+    ///
+    /// Config::write(Some("./.mask"), "4.3.7")?;
+    /// assert!(canonical.is_file());
+    /// assert_eq!(Config::new(None)?.0.0, "4.3.7");
+    /// # std::env::set_current_dir(std::env::temp_dir())?;
+    /// # std::fs::remove_dir_all(dir)
+    /// # }
+    /// ```
+    pub fn canonical_config_path(path: Option<&str>) -> PathBuf {
+        let mut path: PathBuf = PathBuf::from(path.unwrap_or(DEFAULT_CONFIG));
+        if path.is_dir() {
+            path.push(DEFAULT_CONFIG);
+        }
+        std::path::absolute(&path).unwrap_or(path)
+    }
+
+    /// Constructs a configuration purely from the environment.
+    ///
+    /// The first variable of [VERSION_VARS] that is set is used, so
+    /// `MASK_VERSION` wins over `HAXE_VERSION`. Variables that are set to an
+    /// empty value count as unset. [`None`] is returned if neither is set.
+    /// No files are read.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// // SAFETY: No other threads are accessing the environment at this point.
+    /// unsafe {
+    ///     std::env::remove_var("MASK_VERSION");
+    ///     std::env::remove_var("HAXE_VERSION");
+    /// }
+    /// assert!(Config::from_env().is_none());
+    ///
+    /// unsafe { std::env::set_var("HAXE_VERSION", "4.2.5") };
+    /// assert_eq!(Config::from_env().unwrap().0.0, "4.2.5");
+    ///
+    /// unsafe { std::env::set_var("MASK_VERSION", "") };
+    /// assert_eq!(Config::from_env().unwrap().0.0, "4.2.5");
+    ///
+    /// unsafe { std::env::set_var("MASK_VERSION", "4.3.7") };
+    /// assert_eq!(Config::from_env().unwrap().0.0, "4.3.7");
+    /// ```
+    pub fn from_env() -> Option<Config> {
+        Config::version_var(|key| env::var(key).ok())
+            .map(|(_, version)| Config(HaxeVersion(version)))
+    }
+
+    /// Finds the first variable of [VERSION_VARS] that is set to a non-empty value, and returns its name and value.
+    ///
+    /// The variables are read through `env`, as in [resolve](#method.resolve).
+    fn version_var(env: impl Fn(&str) -> Option<String>) -> Option<(&'static str, String)> {
+        VERSION_VARS.iter().find_map(|var| {
+            env(var)
+                .filter(|version| !version.is_empty())
+                .map(|version| (*var, version))
+        })
+    }
+
+    /// Gets the path of the configuration file that is read when no path is specified.
+    ///
+    /// This is [LOCAL_CONFIG] if it exists in the working directory, and
+    /// [DEFAULT_CONFIG] otherwise. Writing never uses the local configuration
+    /// implicitly; see [write](#method.write).
+    pub fn default_path() -> &'static str {
+        if Path::new(LOCAL_CONFIG).is_file() {
+            LOCAL_CONFIG
+        } else {
+            DEFAULT_CONFIG
+        }
+    }
+
+    /// Lists the configuration files that influence a directory, from the highest precedence to the lowest.
+    ///
+    /// These are the files read by [from_ancestors](#method.from_ancestors):
+    /// for `start` and each of its parents up to the root, [LOCAL_CONFIG] if
+    /// it exists there, and [DEFAULT_CONFIG] otherwise. Nothing is read aside
+    /// from checking which files exist.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let outer = std::env::temp_dir().join("libmask-discovery-example");
+    /// # let _ = std::fs::remove_dir_all(&outer);
+    /// let inner = outer.join("inner");
+    /// std::fs::create_dir_all(&inner)?;
+    /// std::fs::write(outer.join(DEFAULT_CONFIG), "4.2.5")?;
+    /// std::fs::write(inner.join(DEFAULT_CONFIG), "4.3.7")?;
+    /// std::fs::write(inner.join(LOCAL_CONFIG), "4.3.6")?;
+    ///
+    /// assert_eq!(
+    ///     Config::discovery_chain(&inner),
+    ///     [inner.join(LOCAL_CONFIG), outer.join(DEFAULT_CONFIG)]
+    /// );
+    /// # std::fs::remove_dir_all(outer)
+    /// # }
+    /// ```
+    pub fn discovery_chain(start: &Path) -> Vec<PathBuf> {
+        start
+            .ancestors()
+            .filter_map(|dir| {
+                [LOCAL_CONFIG, DEFAULT_CONFIG]
+                    .iter()
+                    .map(|name| dir.join(name))
+                    .find(|path| path.is_file())
+            })
+            .collect()
+    }
+
+    /// Finds every configuration file in a directory and all of its subdirectories.
+    ///
+    /// This is the opposite of [discovery_chain](#method.discovery_chain),
+    /// and is meant for auditing a whole repository: both [DEFAULT_CONFIG] and
+    /// [LOCAL_CONFIG] files are returned, sorted by path. Symlinked
+    /// directories aren't followed, so that links can't cause a loop, and
+    /// `.git` directories are skipped.
+    ///
+    /// A subdirectory that can't be read doesn't stop the search; it's
+    /// recorded in [`FoundConfigs::unreadable`] instead, so the caller can
+    /// report it. Only failing to read `root` itself is an error.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-find-all-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// let lib = root.join("libs").join("lib");
+    /// std::fs::create_dir_all(&lib)?;
+    /// std::fs::create_dir_all(root.join(".git"))?;
+    /// std::fs::write(root.join(DEFAULT_CONFIG), "4.3.7")?;
+    /// std::fs::write(lib.join(LOCAL_CONFIG), "4.2.5")?;
+    /// std::fs::write(root.join(".git").join(DEFAULT_CONFIG), "4.2.5")?;
+    ///
+    /// let found = Config::find_all(&root)?;
+    /// assert_eq!(found.files, [root.join(DEFAULT_CONFIG), lib.join(LOCAL_CONFIG)]);
+    /// assert!(found.unreadable.is_empty());
+    ///
+    /// assert!(Config::find_all(&root.join("missing")).is_err());
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn find_all(root: &Path) -> Result<FoundConfigs, Error> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        let mut unreadable: Vec<(PathBuf, Error)> = Vec::new();
+        let mut pending: Vec<PathBuf> = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let entries: fs::ReadDir = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if dir == root => return Err(e),
+                Err(e) => {
+                    unreadable.push((dir, e));
+                    continue;
+                }
+            };
+            for entry in entries {
+                let (entry, file_type): (fs::DirEntry, fs::FileType) = match entry
+                    .and_then(|entry| entry.file_type().map(|file_type| (entry, file_type)))
+                {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        unreadable.push((dir.clone(), e));
+                        break;
+                    }
+                };
+                let name: OsString = entry.file_name();
+                if file_type.is_dir() {
+                    if name != ".git" {
+                        pending.push(entry.path());
+                    }
+                } else if name == DEFAULT_CONFIG || name == LOCAL_CONFIG {
+                    files.push(entry.path());
+                }
+            }
+        }
+        files.sort();
+        unreadable.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(FoundConfigs { files, unreadable })
+    }
+
+    /// Reads the configurations of a directory and all of its parents, and merges them.
+    ///
+    /// Each directory from `start` up to the root contributes at most one
+    /// file: [LOCAL_CONFIG] if it exists there, and [DEFAULT_CONFIG]
+    /// otherwise. The files are layered from the outermost directory down to
+    /// the innermost one, and every field a file specifies overrides the one
+    /// from the directories above it, while the fields it leaves out are
+    /// inherited. Since a configuration currently only holds a version, this
+    /// means the innermost file with a version wins, and a blank file inherits
+    /// the version of its parents.
+    ///
+    /// A file containing [INHERIT_VERSION] is skipped entirely, so it always
+    /// defers to the directories above it. If no file above it has a version,
+    /// the result is the same as if no configuration existed there, so the
+    /// caller falls back to whatever it uses by default.
+    ///
+    /// The merged configuration is returned alongside the path of the file
+    /// that provided its version, or [`None`] if no configuration was found.
+    /// If every file found is blank, the innermost one is returned.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let outer = std::env::temp_dir().join("libmask-ancestors-example");
+    /// # let _ = std::fs::remove_dir_all(&outer);
+    /// let inner = outer.join("inner");
+    /// let deepest = inner.join("deepest");
+    /// std::fs::create_dir_all(&deepest)?;
+    /// std::fs::write(outer.join(DEFAULT_CONFIG), "4.2.5")?;
+    /// std::fs::write(inner.join(DEFAULT_CONFIG), "4.3.7")?;
+    ///
+    /// let (config, path) = Config::from_ancestors(&deepest)?.unwrap();
+    /// assert_eq!(config.0.0, "4.3.7");
+    /// assert_eq!(path, inner.join(DEFAULT_CONFIG));
+    ///
+    /// std::fs::write(inner.join(DEFAULT_CONFIG), "")?;
+    /// let (config, path) = Config::from_ancestors(&deepest)?.unwrap();
+    /// assert_eq!(config.0.0, "4.2.5");
+    /// assert_eq!(path, outer.join(DEFAULT_CONFIG));
+    ///
+    /// // Inheriting files are skipped, wherever they are.
+    /// std::fs::write(deepest.join(DEFAULT_CONFIG), INHERIT_VERSION)?;
+    /// std::fs::write(inner.join(DEFAULT_CONFIG), INHERIT_VERSION)?;
+    /// let (config, path) = Config::from_ancestors(&deepest)?.unwrap();
+    /// assert_eq!(config.0.0, "4.2.5");
+    /// assert_eq!(path, outer.join(DEFAULT_CONFIG));
+    ///
+    /// std::fs::write(outer.join(DEFAULT_CONFIG), INHERIT_VERSION)?;
+    /// std::fs::write(inner.join(DEFAULT_CONFIG), "4.3.7")?;
+    /// assert_eq!(Config::from_ancestors(&deepest)?.unwrap().0.0.0, "4.3.7");
+    /// std::fs::write(inner.join(DEFAULT_CONFIG), INHERIT_VERSION)?;
+    /// assert!(Config::from_ancestors(&deepest)?.is_none());
+    /// # std::fs::remove_dir_all(outer)
+    /// # }
+    /// ```
+    pub fn from_ancestors(start: &Path) -> Result<Option<(Config, PathBuf)>, Error> {
+        let mut merged: Option<(Config, PathBuf)> = None;
+        for file in Config::discovery_chain(start).into_iter().rev() {
+            let version: String = Config::read_from_file(&file.to_string_lossy())?;
+            if version.trim() == INHERIT_VERSION {
+                continue;
+            }
+            let inherits: bool = version.trim().is_empty()
+                && merged
+                    .as_ref()
+                    .is_some_and(|(config, _)| !config.is_empty());
+            if !inherits {
+                merged = Some((Config(HaxeVersion(version)), file));
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Finds the configuration to use, going through every way of specifying one in order of precedence.
+    ///
+    /// This is the single entry point for resolving a configuration, and
+    /// checks the following in order, using the first one that is present:
+    ///
+    /// 1. The explicit version of the [ResolveOptions].
+    /// 2. The [VERSION_VARS], in their order, ignoring empty values.
+    /// 3. The configuration file named by the [ResolveOptions], `MASK_CONFIG_FILE`
+    ///    or `MASK_CONFIG`, as found by [`ResolveOptions::named_config`].
+    /// 4. The configuration files of `start` and its parents, as merged by
+    ///    [from_ancestors](#method.from_ancestors).
+    ///
+    /// Environment variables are read through `env` rather than from the
+    /// process, and relative configuration files are relative to `start`, so
+    /// that the whole chain can be run against any environment. A named file
+    /// always wins over the files of `start` and its parents, which aren't
+    /// looked at at all. Since it was asked for explicitly, a named file that
+    /// doesn't exist produces an [Error] of kind [ErrorKind::NotFound] rather
+    /// than falling back to them. Files that exist but can't be read produce
+    /// the [Error] of [new](#method.new).
+    ///
+    /// The version that was found is then resolved by
+    /// [`HaxeVersion::resolve_chain`], so aliases, `latest` and version
+    /// requirements refer to a concrete installed version. If the
+    /// installations directory doesn't exist, the version is used as-is.
+    /// Blank and [inheriting](#method.inherits) configurations aren't resolved.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-resolve-config-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// let project = root.join("project");
+    /// for version in ["4.2.5", "4.3.7"] {
+    ///     std::fs::create_dir_all(root.join("versions").join(version).join("std"))?;
+    /// }
+    /// std::fs::create_dir_all(&project)?;
+    /// std::fs::write(project.join(DEFAULT_CONFIG), "latest")?;
+    /// std::fs::write(project.join("other.mask"), "4.2.5")?;
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", root.join("versions"));
+    ///     std::env::remove_var("MASK_STD_SUBDIR");
+    /// }
+    ///
+    /// let none = |_: &str| None;
+    /// let resolved = Config::resolve(&project, none, &ResolveOptions::default())?;
+    /// assert_eq!(resolved.source, ConfigSource::Ancestors);
+    /// assert_eq!(resolved.requested.unwrap().0, "latest");
+    /// assert_eq!(resolved.config.unwrap().0.0, "4.3.7");
+    /// assert_eq!(resolved.path, Some(project.join(DEFAULT_CONFIG)));
+    ///
+    /// let env = |key: &str| (key == "MASK_CONFIG").then(|| "other.mask".to_string());
+    /// let resolved = Config::resolve(&project, env, &ResolveOptions::default())?;
+    /// assert_eq!(resolved.source, ConfigSource::ConfigVariable);
+    /// assert_eq!(resolved.config.unwrap().0.0, "4.2.5");
+    ///
+    /// let env = |key: &str| (key == "MASK_VERSION").then(|| "4.2.5".to_string());
+    /// let options = ResolveOptions {
+    ///     explicit: Some("4.3.7".into()),
+    ///     config: None,
+    /// };
+    /// let resolved = Config::resolve(&project, env, &options)?;
+    /// assert_eq!(resolved.source, ConfigSource::Explicit);
+    /// assert_eq!(resolved.config.unwrap().0.0, "4.3.7");
+    /// let resolved = Config::resolve(&project, env, &ResolveOptions::default())?;
+    /// assert_eq!(resolved.source, ConfigSource::Environment("MASK_VERSION"));
+    ///
+    /// // An empty variable is ignored, rather than naming an empty version.
+    /// let env = |key: &str| (key == "HAXE_VERSION").then(String::new);
+    /// let resolved = Config::resolve(&project, env, &ResolveOptions::default())?;
+    /// assert_eq!(resolved.source, ConfigSource::Ancestors);
+    /// assert_eq!(resolved.config.unwrap().0.0, "4.3.7");
+    ///
+    /// // A named file wins over discovery, and is never skipped if it's missing.
+    /// let options = ResolveOptions {
+    ///     explicit: None,
+    ///     config: Some("other.mask".into()),
+    /// };
+    /// let resolved = Config::resolve(&project, none, &options)?;
+    /// assert_eq!(resolved.source, ConfigSource::ConfigOption);
+    /// assert_eq!(resolved.config.unwrap().0.0, "4.2.5");
+    /// assert_eq!(resolved.path, Some(project.join("other.mask")));
+    ///
+    /// let options = ResolveOptions {
+    ///     explicit: None,
+    ///     config: Some("missing.mask".into()),
+    /// };
+    /// let error = Config::resolve(&project, none, &options).err().unwrap();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    /// let env = |key: &str| (key == "MASK_CONFIG_FILE").then(|| "missing.mask".to_string());
+    /// let error = Config::resolve(&project, env, &ResolveOptions::default()).err().unwrap();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn resolve(
+        start: &Path,
+        env: impl Fn(&str) -> Option<String>,
+        options: &ResolveOptions,
+    ) -> Result<ResolvedConfig, Error> {
+        let (config, source, path): (Option<Config>, ConfigSource, Option<PathBuf>) =
+            if let Some(version) = &options.explicit {
+                (
+                    Some(Config(HaxeVersion(version.clone()))),
+                    ConfigSource::Explicit,
+                    None,
+                )
+            } else if let Some((var, version)) = Config::version_var(&env) {
+                (
+                    Some(Config(HaxeVersion(version))),
+                    ConfigSource::Environment(var),
+                    None,
+                )
+            } else if let Some((file, source)) = options.named_config(&env) {
+                let path: PathBuf = start.join(&file);
+                let config: Config = Config::new(Some(&path.to_string_lossy())).map_err(|e| {
+                    // The file was asked for explicitly, so its absence is an error as well.
+                    if e.kind() == ErrorKind::NotFound {
+                        Error::new(e.kind(), format!("{}, but the {} names it", e, source))
+                    } else {
+                        e
+                    }
+                })?;
+                (Some(config), source, Some(path))
+            } else {
+                match Config::from_ancestors(start)? {
+                    Some((config, path)) => (Some(config), ConfigSource::Ancestors, Some(path)),
+                    None => (None, ConfigSource::Default, None),
+                }
+            };
+
+        let requested: Option<HaxeVersion> = config.as_ref().map(|config| config.0.clone());
+        let mut steps: Vec<String> = Vec::new();
+        let config: Option<Config> = match config {
+            Some(config) if !config.is_empty() => match config.0.resolve_chain() {
+                Ok((version, taken)) => {
+                    steps = taken;
+                    Some(Config(version))
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => Some(config),
+                Err(e) => return Err(e),
+            },
+            config => config,
+        };
+        Ok(ResolvedConfig {
+            config,
+            requested,
+            steps,
+            source,
+            path,
+        })
+    }
+
+    /// Reads a configuration the same way as [new](#method.new), and fingerprints the file it was read from.
+    ///
+    /// The [Fingerprint] can later be used to detect whether the file was
+    /// edited after it was read. It's taken before the file is read, so that
+    /// an edit during reading is still reported as stale.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let path = std::env::temp_dir().join("libmask-fingerprint-example.mask");
+    /// let path = path.to_str().unwrap();
+    /// Config::write(Some(path), "4.2.5")?;
+    ///
+    /// let (config, fingerprint) = Config::new_fingerprinted(Some(path))?;
+    /// assert_eq!(config.0.0, "4.2.5");
+    /// assert!(!fingerprint.is_stale());
+    ///
+    /// Config::write(Some(path), "4.3.7-rc")?;
+    /// assert!(fingerprint.is_stale());
+    /// # std::fs::remove_file(path)
+    /// # }
+    /// ```
+    pub fn new_fingerprinted(path: Option<&str>) -> Result<(Config, Fingerprint), Error> {
+        let fingerprint: Fingerprint = Fingerprint::new(Path::new(&Config::reading_path(path)))?;
+        Ok((Config::new(path)?, fingerprint))
     }
 
-    /// Checks if a Haxe version is properly installed, and returns its path if it is.
+    /// Reads a configuration the same way as [new](#method.new), but falls back to a caller-supplied version if the file doesn't exist.
     ///
-    /// This works the same as [get_path](#method.get_path), but checks for the
-    /// existence of both the Haxe version and its standard library before
-    /// proceeding to return the path.
-    pub fn get_path_installed(&self) -> Result<PathBuf, Error> {
-        if self.get_std_path()?.try_exists()? {
-            Ok(self.get_path()?)
-        } else {
-            Err(Error::new(
-                ErrorKind::NotFound,
-                format!(
-                    "Haxe version {} could not be found using the standard library",
-                    self.0
-                ),
-            ))
+    /// Unlike the [`Default`] implementation, which is only available in debug
+    /// builds and uses a hardcoded version, this lets the caller decide what
+    /// the fallback should be. Any other error, such as a file that can't be
+    /// read, is returned instead of being replaced by the fallback.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// let dir = std::env::temp_dir().join("libmask-load-or-example");
+    /// # let _ = std::fs::remove_dir_all(&dir);
+    /// std::fs::create_dir_all(&dir).unwrap();
+    ///
+    /// let missing = dir.join("missing.mask");
+    /// let config = Config::load_or(missing.to_str(), HaxeVersion("4.3.7".into())).unwrap();
+    /// assert_eq!(config.0.0, "4.3.7");
+    ///
+    /// // The file exists, but doesn't hold a single version.
+    /// let conflicting = dir.join("conflicting.mask");
+    /// std::fs::write(&conflicting, "4.2.5\n4.3.7\n").unwrap();
+    /// assert!(Config::load_or(conflicting.to_str(), HaxeVersion("4.3.7".into())).is_err());
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn load_or(path: Option<&str>, default: HaxeVersion) -> Result<Config, Error> {
+        match Config::new(path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config(default)),
+            result => result,
         }
     }
-}
 
-#[derive(Clone)]
-/// A basic representation of a `libmask` configuration.
-pub struct Config(pub HaxeVersion);
+    /// Checks whether the configuration is effectively blank.
+    ///
+    /// A configuration is considered empty if its version contains nothing
+    /// but whitespace, since such a version can never refer to a valid Haxe
+    /// installation. A configuration that [inherits](#method.inherits) its
+    /// version is empty as well, since it doesn't pick one on its own.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// assert!(Config(HaxeVersion("".into())).is_empty());
+    /// assert!(Config(HaxeVersion("  \t ".into())).is_empty());
+    /// assert!(Config(HaxeVersion(INHERIT_VERSION.into())).is_empty());
+    /// assert!(!Config(HaxeVersion("4.2.5".into())).is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.0.0.trim().is_empty() || self.inherits()
+    }
 
-impl Config {
-    /// This reads a sample configuration from the disk, and returns it if it's valid as a [Result].
-    pub fn new(path: Option<&str>) -> Result<Config, Error> {
-        let version: String = Config::read_from_file(path.unwrap_or(".mask"))?;
-        Ok(Config(HaxeVersion(version)))
+    /// Checks whether the configuration uses the version of its parent directories.
+    ///
+    /// This is the case if its version is [INHERIT_VERSION], ignoring
+    /// surrounding whitespace.
+    pub fn inherits(&self) -> bool {
+        self.0.0.trim() == INHERIT_VERSION
     }
 
     /// Checks a configuration path's validity and whether or not it exists, returning the path if it exists.
@@ -180,34 +2088,215 @@ impl Config {
         } else {
             Err(Error::new(
                 ErrorKind::NotFound,
-                format!("Configuration file \"{}\" does not exist", path.display()),
+                format!(
+                    "Configuration file \"{}\" does not exist",
+                    display_path(path)
+                ),
             ))
         }
     }
 
     /// Reads a file from a disk, returning its contents according to
     /// [read_to_string](std::fs::read_to_string).
+    ///
+    /// Newlines are stripped from the contents. Blank lines are ignored, but
+    /// a file containing more than one different version fails with an
+    /// [ErrorKind::InvalidData] error that points at the conflicting lines.
+    /// Errors include the path of the file.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// let path = std::env::temp_dir().join("libmask-conflict-example.mask");
+    /// let path = path.to_str().unwrap();
+    /// std::fs::write(path, "4.2.5\n\n4.3.7\n").unwrap();
+    ///
+    /// let error = Config::read_from_file(path).unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    /// assert!(error.to_string().contains("lines 1 and 3"));
+    /// # std::fs::remove_file(path).unwrap();
+    /// ```
     pub fn read_from_file(supposed_path: &str) -> Result<String, Error> {
-        match Config::path(supposed_path) {
-            Ok(path) => {
-                let mut contents: String = fs::read_to_string(path)?;
-                contents.retain(|c| c != '\n');
-                Ok(contents)
+        let path: &Path = Config::path(supposed_path)?;
+        let contents: String = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!(
+                    "Configuration file \"{}\" could not be read: {}",
+                    display_path(path),
+                    e
+                ),
+            )
+        })?;
+
+        let mut version: Option<(usize, &str)> = None;
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match version {
+                None => version = Some((index + 1, line)),
+                Some((_, first)) if first == line => {}
+                Some((first_line, first)) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Configuration file \"{}\" contains conflicting Haxe versions \
+                            on lines {} and {}: \"{}\" and \"{}\"",
+                            display_path(path),
+                            first_line,
+                            index + 1,
+                            first,
+                            line
+                        ),
+                    ));
+                }
             }
-            Err(e) => Err(e),
         }
+        Ok(version
+            .map(|(_, line)| line.to_string())
+            .unwrap_or_default())
     }
 
     /// Writes the configuration to a specified path.
+    ///
+    /// If no path is specified, [DEFAULT_CONFIG] is written to, even if a
+    /// [LOCAL_CONFIG] exists. Any missing parent directories of the path are
+    /// created beforehand. If the path is a directory, [DEFAULT_CONFIG] inside
+    /// of it is written to. The version is followed by a single newline, as is
+    /// conventional for text files; use [write_exact](#method.write_exact) to
+    /// leave it out.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-nested-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// let path = root.join("configs").join(".mask");
+    /// Config::write(path.to_str(), "4.3.7")?;
+    /// assert_eq!(std::fs::read(&path)?, b"4.3.7\n");
+    /// assert_eq!(Config::new(path.to_str())?.0.0, "4.3.7");
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
     pub fn write(path: Option<&str>, version: &str) -> Result<(), Error> {
-        fs::write(path.unwrap_or(".mask"), version)?;
+        Config::write_exact(path, &format!("{}\n", version))
+    }
+
+    /// Operates the same way as [write](#method.write), but writes the version as-is, without a trailing newline.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let path = std::env::temp_dir().join("libmask-exact-example.mask");
+    /// Config::write_exact(path.to_str(), "4.3.7")?;
+    /// assert_eq!(std::fs::read(&path)?, b"4.3.7");
+    /// # std::fs::remove_file(path)
+    /// # }
+    /// ```
+    pub fn write_exact(path: Option<&str>, version: &str) -> Result<(), Error> {
+        let path: PathBuf = Config::canonical_config_path(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, version)?;
         Ok(())
     }
 
     /// Operates under the same conditions as [write](#method.write), except checking the Haxe version's existence beforehand.
     pub fn safe_write(path: Option<&str>, version: &str) -> Result<(), Error> {
+        Config::check_version_exists(version)?;
+        Config::write(path, version)
+    }
+
+    /// Checks whether [safe_write](#method.safe_write) would succeed, without writing anything.
+    ///
+    /// This performs the same check of the Haxe version's existence, followed
+    /// by [check_writable](#method.check_writable), and returns the [Error]
+    /// that would prevent the write, if any.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// let root = std::env::temp_dir().join("libmask-check-write-example");
+    /// # let _ = std::fs::remove_dir_all(&root);
+    /// std::fs::create_dir_all(root.join("4.3.7").join("std"))?;
+    /// unsafe { std::env::set_var("MASK_HAXE_DIR", &root) };
+    ///
+    /// let path = root.join("project").join(DEFAULT_CONFIG);
+    /// Config::check_write(path.to_str(), "4.3.7")?;
+    /// assert!(!path.exists());
+    ///
+    /// let error = Config::check_write(path.to_str(), "4.2.5").unwrap_err();
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    /// # std::fs::remove_dir_all(root)
+    /// # }
+    /// ```
+    pub fn check_write(path: Option<&str>, version: &str) -> Result<(), Error> {
+        Config::check_version_exists(version)?;
+        Config::check_writable(path)
+    }
+
+    /// Checks whether the configuration file at a path could be written to, without writing anything.
+    ///
+    /// The path is resolved with
+    /// [canonical_config_path](#method.canonical_config_path). An existing
+    /// file is opened for appending, which leaves its contents alone, while a
+    /// missing file requires its closest existing parent directory to be
+    /// writable. That directory is checked by creating and removing a probe
+    /// file in it, since its permissions alone don't tell whether the current
+    /// user may write to it.
+    pub fn check_writable(path: Option<&str>) -> Result<(), Error> {
+        let path: PathBuf = Config::canonical_config_path(path);
+        if path.exists() {
+            return fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .map(|_| ())
+                .map_err(|e| {
+                    Error::new(
+                        e.kind(),
+                        format!("Could not write to \"{}\": {}", display_path(&path), e),
+                    )
+                });
+        }
+
+        let Some(parent) = path.ancestors().skip(1).find(|dir| dir.exists()) else {
+            return Ok(());
+        };
+        if !parent.is_dir() {
+            return Err(Error::new(
+                ErrorKind::NotADirectory,
+                format!(
+                    "Could not create \"{}\", since \"{}\" is not a directory",
+                    display_path(&path),
+                    display_path(parent)
+                ),
+            ));
+        }
+        let probe: PathBuf = parent.join(format!(".mask-write-probe-{}", std::process::id()));
+        fs::File::create_new(&probe)
+            .and_then(|_| fs::remove_file(&probe))
+            .map_err(|e| {
+                Error::new(
+                    e.kind(),
+                    format!(
+                        "Could not create \"{}\", since \"{}\" is not writable: {}",
+                        display_path(&path),
+                        display_path(parent),
+                        e
+                    ),
+                )
+            })
+    }
+
+    /// Checks that a Haxe version exists, as required before switching a configuration to it.
+    fn check_version_exists(version: &str) -> Result<(), Error> {
         if HaxeVersion::get_version(version)?.try_exists()? {
-            Config::write(path, version)
+            Ok(())
         } else {
             Err(Error::new(
                 ErrorKind::NotFound,
@@ -217,6 +2306,148 @@ impl Config {
     }
 }
 
+/// The inputs of [`Config::resolve`] that are given by the user rather than the environment.
+#[derive(Clone, Debug, Default)]
+pub struct ResolveOptions {
+    /// A Haxe version given explicitly, which takes precedence over everything else.
+    pub explicit: Option<String>,
+    /// A configuration file given explicitly, which takes precedence over the
+    /// environment variables that name one.
+    pub config: Option<String>,
+}
+
+impl ResolveOptions {
+    /// Finds the configuration file named by the options or the environment, alongside where it was named.
+    ///
+    /// The file of the options comes first, followed by `MASK_CONFIG_FILE`
+    /// if it isn't empty, and `MASK_CONFIG`. Environment variables are read
+    /// through `env`. Programs that write configurations can use this to write
+    /// to the same file that [`Config::resolve`] reads from.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// let env = |key: &str| match key {
+    ///     "MASK_CONFIG_FILE" => Some(String::new()),
+    ///     "MASK_CONFIG" => Some("lib.mask".to_string()),
+    ///     _ => None,
+    /// };
+    /// let (file, source) = ResolveOptions::default().named_config(env).unwrap();
+    /// assert_eq!(file, "lib.mask");
+    /// assert_eq!(source, ConfigSource::ConfigVariable);
+    /// assert!(ResolveOptions::default().named_config(|_| None).is_none());
+    /// ```
+    pub fn named_config(
+        &self,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<(String, ConfigSource)> {
+        if let Some(file) = &self.config {
+            Some((file.clone(), ConfigSource::ConfigOption))
+        } else if let Some(file) = env("MASK_CONFIG_FILE").filter(|file| !file.is_empty()) {
+            Some((file, ConfigSource::ConfigFileVariable))
+        } else {
+            env("MASK_CONFIG").map(|file| (file, ConfigSource::ConfigVariable))
+        }
+    }
+}
+
+/// Where the configuration returned by [`Config::resolve`] came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The explicit version of the [ResolveOptions].
+    Explicit,
+    /// One of the [VERSION_VARS], which is named.
+    Environment(&'static str),
+    /// The configuration file of the [ResolveOptions].
+    ConfigOption,
+    /// The configuration file named by `MASK_CONFIG_FILE`.
+    ConfigFileVariable,
+    /// The configuration file named by `MASK_CONFIG`.
+    ConfigVariable,
+    /// The configuration files of a directory and its parents.
+    Ancestors,
+    /// No configuration was found.
+    Default,
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Explicit => write!(f, "explicit version"),
+            ConfigSource::Environment(var) => write!(f, "{} environment variable", var),
+            ConfigSource::ConfigOption => write!(f, "configuration file option"),
+            ConfigSource::ConfigFileVariable => write!(f, "MASK_CONFIG_FILE environment variable"),
+            ConfigSource::ConfigVariable => write!(f, "MASK_CONFIG environment variable"),
+            ConfigSource::Ancestors => write!(f, "configuration file"),
+            ConfigSource::Default => write!(f, "default configuration file"),
+        }
+    }
+}
+
+/// A configuration found by [`Config::resolve`], alongside how it was found.
+#[derive(Clone)]
+pub struct ResolvedConfig {
+    /// The configuration, with its version resolved to a concrete one, or
+    /// [`None`] if no configuration was found.
+    pub config: Option<Config>,
+    /// The version as it was specified, before it was resolved.
+    pub requested: Option<HaxeVersion>,
+    /// The steps taken to resolve the version, as described by
+    /// [`HaxeVersion::resolve_chain`].
+    pub steps: Vec<String>,
+    /// Where the configuration came from.
+    pub source: ConfigSource,
+    /// The configuration file that was read or named, if any.
+    pub path: Option<PathBuf>,
+}
+
+/// The configuration files found by [`Config::find_all`].
+pub struct FoundConfigs {
+    /// The configuration files, sorted by path.
+    pub files: Vec<PathBuf>,
+    /// The directories that couldn't be searched, alongside why.
+    pub unreadable: Vec<(PathBuf, Error)>,
+}
+
+/// A snapshot of a configuration file's metadata, used to detect external edits.
+///
+/// Long-running programs that hold on to a [Config] can use this to reload it
+/// before acting on a stale version. Fingerprints only exist for
+/// configurations read from a file; see [`Config::new_fingerprinted`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Fingerprint {
+    /// Captures the modification time and size of a file.
+    pub fn new(path: &Path) -> Result<Fingerprint, Error> {
+        let metadata: fs::Metadata = fs::metadata(path)?;
+        Ok(Fingerprint {
+            path: path.to_path_buf(),
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+
+    /// Gets the path of the fingerprinted file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Checks whether the file changed since it was fingerprinted.
+    ///
+    /// A file that can no longer be accessed is considered stale as well.
+    pub fn is_stale(&self) -> bool {
+        match Fingerprint::new(&self.path) {
+            Ok(current) => current != *self,
+            Err(_) => true,
+        }
+    }
+}
+
 #[cfg(debug_assertions)]
 impl Default for Config {
     fn default() -> Config {
@@ -224,36 +2455,605 @@ impl Default for Config {
     }
 }
 
+/// Formats a path for messages shown to the user.
+///
+/// The path is canonicalized where possible, so that every message refers to
+/// the same location in the same way, regardless of how the path was built.
+/// Paths that can't be canonicalized, such as ones that don't exist yet, are
+/// shown as-is.
+///
+/// ```rust
+/// use libmask::display_path;
+/// use std::path::Path;
+///
+/// let dir = std::env::temp_dir();
+/// assert_eq!(display_path(&dir.join(".")), display_path(&dir));
+/// assert_eq!(display_path(Path::new("missing/../file")), "missing/../file");
+/// ```
+pub fn display_path(path: &Path) -> String {
+    match fs::canonicalize(path) {
+        Ok(canonical) => {
+            let canonical: String = canonical.display().to_string();
+            // Windows canonicalizes into verbatim paths, which are rarely seen elsewhere.
+            #[cfg(windows)]
+            if let Some(stripped) = canonical.strip_prefix(r"\\?\")
+                && !stripped.starts_with("UNC\\")
+            {
+                return stripped.to_string();
+            }
+            canonical
+        }
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// Appends the platform's executable extension to the name of a program.
+///
+/// Every program resolved under a version directory goes through this, so
+/// that, for example, `haxe` resolves to `haxe.exe` on Windows. Names that
+/// already have the extension are returned unchanged.
+///
+/// ```rust
+/// use libmask::executable_name;
+///
+/// if cfg!(windows) {
+///     assert_eq!(executable_name("haxe"), "haxe.exe");
+/// } else {
+///     assert_eq!(executable_name("haxe"), "haxe");
+/// }
+/// assert_eq!(executable_name(&executable_name("haxelib")), executable_name("haxelib"));
+/// ```
+pub fn executable_name(base: &str) -> String {
+    let suffix: &str = env::consts::EXE_SUFFIX;
+    if suffix.is_empty() || base.ends_with(suffix) {
+        base.to_string()
+    } else {
+        format!("{}{}", base, suffix)
+    }
+}
+
+/// Splits a string into arguments, using a small subset of shell quoting.
+///
+/// Arguments are separated by whitespace. Single quotes keep everything up to
+/// the closing quote as-is, while double quotes keep whitespace but still let
+/// a backslash escape `"` and `\`. Outside of quotes, a backslash escapes the
+/// next character. Nothing else, such as variable expansion or globbing, is
+/// performed. An unterminated quote or a trailing backslash is reported as an
+/// [ErrorKind::InvalidInput] error.
+///
+/// ```rust
+/// use libmask::split_args;
+///
+/// assert!(split_args("").unwrap().is_empty());
+/// assert!(split_args("  \t ").unwrap().is_empty());
+/// assert_eq!(split_args("-D  foo -v").unwrap(), ["-D", "foo", "-v"]);
+/// assert_eq!(split_args("-D 'a b' \"c \\\" d\"").unwrap(), ["-D", "a b", "c \" d"]);
+/// assert_eq!(split_args("'it'\\''s' \"\"").unwrap(), ["it's", ""]);
+/// assert_eq!(split_args("a\\ b").unwrap(), ["a b"]);
+/// assert_eq!(
+///     split_args("-D 'open").err().unwrap().kind(),
+///     std::io::ErrorKind::InvalidInput
+/// );
+/// ```
+pub fn split_args(line: &str) -> Result<Vec<String>, Error> {
+    let mut args: Vec<String> = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(arg) = current.take() {
+                    args.push(arg);
+                }
+            }
+            '\'' => {
+                let arg: &mut String = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err(unterminated("single quote", line)),
+                    }
+                }
+            }
+            '"' => {
+                let arg: &mut String = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => arg.push(c),
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => return Err(unterminated("double quote", line)),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err(unterminated("double quote", line)),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => current.get_or_insert_with(String::new).push(c),
+                None => return Err(unterminated("escape", line)),
+            },
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+/// Creates the error reported by [split_args] when the input ends too early.
+fn unterminated(what: &str, line: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("Unterminated {} in arguments: {}", what, line),
+    )
+}
+
+/// The `PATH` used when a program doesn't inherit the parent's environment.
+///
+/// This only contains the directories that system programs typically reside
+/// in, so that the dependencies of executed programs can still be resolved.
+#[cfg(windows)]
+const MINIMAL_PATH: &str = r"C:\Windows\System32;C:\Windows";
+#[cfg(not(windows))]
+const MINIMAL_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+/// Recursively copies the contents of a directory into an existing one.
+///
+/// [fs::copy] keeps the permissions of every file. Symbolic links are copied
+/// as links on Unix, and followed elsewhere.
+fn copy_dir_contents(source: &Path, dest: &Path) -> Result<(), Error> {
+    for entry in fs::read_dir(source)? {
+        let entry: fs::DirEntry = entry?;
+        let from: PathBuf = entry.path();
+        let to: PathBuf = dest.join(entry.file_name());
+        let file_type: fs::FileType = entry.file_type()?;
+        #[cfg(unix)]
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(&from)?, &to)?;
+            continue;
+        }
+        if from.is_dir() {
+            fs::create_dir(&to)?;
+            copy_dir_contents(&from, &to)?;
+        } else {
+            fs::copy(&from, &to)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prepends a version directory to a `PATH`-like string.
+fn prepend_path(path: &Path, base: &OsStr) -> OsString {
+    let mut joined: OsString = path.as_os_str().to_os_string();
+    if !base.is_empty() {
+        joined.push(if cfg!(windows) { ";" } else { ":" });
+        joined.push(base);
+    }
+    joined
+}
+
 /// Attempts to create a [Command] that has its `PATH` prepended with a [Config]'s version directory.
 ///
-/// This method can be independently used in order to run custom commands, or
-/// to customize how the [Command] should run.
+/// The rest of the variables provided by
+/// [`HaxeVersion::env_vars`] are set as well. This method can be
+/// independently used in order to run custom commands, or to customize how
+/// the [Command] should run.
 pub fn create_patched_cmd(
     args: Vec<String>,
     config: Config,
     prog: PathBuf,
 ) -> Result<Command, Error> {
     let mut cmd: Command = Command::new(prog);
-    let path: PathBuf = config.0.get_path()?;
-    cmd.args(args).env(
-        "PATH",
-        if cfg!(windows) {
-            format!(
-                "{};{}",
-                path.display(),
-                env::var("PATH").unwrap_or("".to_string())
-            )
-        } else {
-            format!(
-                "{}:{}",
-                path.display(),
-                env::var("PATH").unwrap_or("".to_string())
-            )
-        },
-    );
+    cmd.args(args).envs(config.0.env_vars()?);
     Ok(cmd)
 }
 
+/// The program that an [ExecBuilder] executes.
+#[derive(Clone)]
+enum Program {
+    /// A program relative to the version directory.
+    Versioned(String),
+    /// A program looked up using the patched `PATH`.
+    External(String),
+    /// An exact path to a program.
+    Path(PathBuf),
+}
+
+/// Builder that describes how a program under a version directory is executed.
+///
+/// [`haxe_exec`] is a shorthand over this builder; the builder itself is
+/// useful when the execution needs more control, such as running the program
+/// through a wrapper.
+#[derive(Clone)]
+pub struct ExecBuilder {
+    config: Config,
+    prog: Program,
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    wrapper: Option<String>,
+    inherit_env: bool,
+    env_allowlist: Option<Vec<String>>,
+}
+
+impl ExecBuilder {
+    /// Creates a builder that runs the Haxe compiler of a [Config]'s version.
+    ///
+    /// The wrapper program is initially taken from the `MASK_EXEC_WRAPPER`
+    /// environment variable, if it's set and not empty.
+    pub fn new(config: Config) -> ExecBuilder {
+        ExecBuilder {
+            config,
+            prog: Program::Versioned("haxe".to_string()),
+            args: Vec::new(),
+            envs: Vec::new(),
+            wrapper: env::var("MASK_EXEC_WRAPPER")
+                .ok()
+                .filter(|wrapper| !wrapper.is_empty()),
+            inherit_env: true,
+            env_allowlist: None,
+        }
+    }
+
+    /// Sets the program to execute, relative to the version directory.
+    ///
+    /// The platform's executable extension is appended to the program's name
+    /// through [executable_name].
+    pub fn prog(mut self, prog: &str) -> ExecBuilder {
+        self.prog = Program::Versioned(prog.to_string());
+        self
+    }
+
+    /// Sets an exact path to the program to execute, bypassing its resolution under the version directory.
+    ///
+    /// The version doesn't need to be installed, but its environment is still
+    /// applied, so this can be used to run a locally-built compiler against
+    /// the standard library of a version. The path has to exist.
+    pub fn prog_path(mut self, prog: PathBuf) -> ExecBuilder {
+        self.prog = Program::Path(prog);
+        self
+    }
+
+    /// Sets a program to execute that isn't part of the version directory.
+    ///
+    /// The program is looked up using the patched `PATH`, and the version
+    /// doesn't need to be installed for it to run. Wrappers are never applied
+    /// to external programs.
+    pub fn external_prog(mut self, prog: &str) -> ExecBuilder {
+        self.prog = Program::External(prog.to_string());
+        self
+    }
+
+    /// Sets the arguments passed to the program.
+    pub fn args(mut self, args: Vec<String>) -> ExecBuilder {
+        self.args = args;
+        self
+    }
+
+    /// Adds an environment variable that is set for the program.
+    pub fn env(mut self, key: &str, value: &str) -> ExecBuilder {
+        self.envs.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets a wrapper program that the resolved program is executed through.
+    ///
+    /// The wrapper receives the path to the resolved program as its first
+    /// argument, followed by the program's own arguments. This is mainly
+    /// useful for systems that need a runner to execute dynamically-linked
+    /// binaries, such as `steam-run` on [NixOS](https://nixos.org/).
+    /// Passing [`None`] disables any wrapper, including the one provided by
+    /// `MASK_EXEC_WRAPPER`.
+    pub fn wrapper(mut self, wrapper: Option<String>) -> ExecBuilder {
+        self.wrapper = wrapper;
+        self
+    }
+
+    /// Sets whether the program inherits the environment of the current process.
+    ///
+    /// This is enabled by default. When disabled, the program only receives
+    /// the variables set through [env](#method.env) and the ones from
+    /// [`HaxeVersion::env_vars`], where the `PATH` contains the version
+    /// directory followed by the typical system directories. This prevents
+    /// the host environment from leaking into builds.
+    pub fn inherit_env(mut self, inherit_env: bool) -> ExecBuilder {
+        self.inherit_env = inherit_env;
+        self
+    }
+
+    /// Limits the inherited environment to the variables with the given names.
+    ///
+    /// This sits between full inheritance and [inherit_env](#method.inherit_env)
+    /// being disabled: the program receives the listed variables that are
+    /// set in the current process, alongside the ones set through
+    /// [env](#method.env) and the ones from [`HaxeVersion::env_vars`]. The
+    /// version directory is prepended to the inherited `PATH` if it's listed,
+    /// and to the typical system directories otherwise. Disabling
+    /// [inherit_env](#method.inherit_env) takes precedence over the list.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    /// use std::collections::BTreeSet;
+    ///
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// unsafe {
+    ///     std::env::set_var("MASK_HAXE_DIR", std::env::temp_dir().join("libmask-allowlist-example"));
+    ///     std::env::set_var("LIBMASK_ALLOWED", "1");
+    ///     std::env::set_var("LIBMASK_DENIED", "1");
+    ///     std::env::remove_var("LIBMASK_UNSET");
+    /// }
+    /// let cmd = ExecBuilder::new(Config(HaxeVersion("4.3.7".into())))
+    ///     .external_prog("haxe")
+    ///     .env("LIBMASK_EXTRA", "1")
+    ///     .env_allowlist(&["LIBMASK_ALLOWED", "LIBMASK_UNSET"])
+    ///     .build()?;
+    /// let vars: BTreeSet<String> = cmd
+    ///     .get_envs()
+    ///     .filter(|(_, value)| value.is_some())
+    ///     .map(|(key, _)| key.to_string_lossy().into_owned())
+    ///     .collect();
+    /// assert_eq!(
+    ///     vars,
+    ///     ["HAXE_STD_PATH", "LIBMASK_ALLOWED", "LIBMASK_EXTRA", "PATH"]
+    ///         .map(String::from)
+    ///         .into()
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn env_allowlist(mut self, names: &[&str]) -> ExecBuilder {
+        self.env_allowlist = Some(names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    /// Resolves the program under the version directory, and creates the [Command] for it.
+    ///
+    /// This will fail if either the version or the program isn't available.
+    pub fn build(self) -> Result<Command, Error> {
+        let version: HaxeVersion = self.config.0.clone();
+        let external: bool = matches!(self.prog, Program::External(_));
+        let prog_buf: PathBuf = match self.prog {
+            Program::External(prog) => prog.into(),
+            Program::Versioned(prog) => self
+                .config
+                .0
+                .get_path_installed()?
+                .join(executable_name(&prog)),
+            Program::Path(prog) => prog,
+        };
+        let mut cmd: Command = if external {
+            create_patched_cmd(self.args, self.config, prog_buf)?
+        } else {
+            if !prog_buf.try_exists()? {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "Program at file location \"{}\" does not exist",
+                        display_path(&prog_buf)
+                    ),
+                ));
+            }
+
+            match self.wrapper {
+                Some(wrapper) => {
+                    let mut args: Vec<String> = Vec::with_capacity(self.args.len() + 1);
+                    args.push(prog_buf.display().to_string());
+                    args.extend(self.args);
+                    create_patched_cmd(args, self.config, wrapper.into())?
+                }
+                None => create_patched_cmd(self.args, self.config, prog_buf)?,
+            }
+        };
+
+        if !self.inherit_env {
+            cmd.env_clear()
+                .envs(version.env_vars_with_path(OsStr::new(MINIMAL_PATH))?);
+        } else if let Some(allowlist) = self.env_allowlist {
+            let base: OsString = match allowlist.iter().any(|name| name == "PATH") {
+                true => env::var_os("PATH").unwrap_or_default(),
+                false => OsString::from(MINIMAL_PATH),
+            };
+            cmd.env_clear();
+            for name in allowlist {
+                if let Some(value) = env::var_os(&name) {
+                    cmd.env(name, value);
+                }
+            }
+            cmd.envs(version.env_vars_with_path(&base)?);
+        }
+        cmd.envs(self.envs);
+        Ok(cmd)
+    }
+
+    /// Builds the [Command] and executes it with all standard `stdio` streams inherited.
+    ///
+    /// Errors that occur once the [Command] is built carry an [ExecFailure],
+    /// which tells a program that couldn't be started apart from one that was
+    /// started, but couldn't be waited for.
+    ///
+    /// ```rust
+    /// use libmask::*;
+    ///
+    /// let error = ExecBuilder::new(Config(HaxeVersion("4.3.7".into())))
+    ///     .external_prog("libmask-nonexistent-program")
+    ///     .exec()
+    ///     .err()
+    ///     .unwrap();
+    /// assert!(matches!(ExecFailure::of(&error), Some(ExecFailure::Spawn(_))));
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    /// ```
+    pub fn exec(self) -> Result<ExecOutcome, Error> {
+        let mut cmd: Command = self.build()?;
+        let start: Instant = Instant::now();
+        let status: ExitStatus = cmd
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(ExecFailure::spawn)?
+            .wait()
+            .map_err(ExecFailure::wait)?;
+        Ok(ExecOutcome {
+            output: Output {
+                status,
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            },
+            duration: start.elapsed(),
+        })
+    }
+
+    /// Builds the [Command] and executes it, capturing its output while also forwarding it.
+    ///
+    /// Both `stdout` and `stderr` are read on separate threads, so neither
+    /// stream can block the other. Every chunk read is written through to the
+    /// respective stream of the current process as soon as it arrives, and is
+    /// also kept in the returned [ExecOutcome]. Failing to forward output, such as
+    /// when the current process's `stdout` is closed, doesn't stop it from
+    /// being captured. `stdin` is inherited.
+    ///
+    /// Errors carry an [ExecFailure] the same way as with [exec](#method.exec),
+    /// where failing to read the output counts as failing to wait.
+    pub fn exec_tee(self) -> Result<ExecOutcome, Error> {
+        let mut cmd: Command = self.build()?;
+        let start: Instant = Instant::now();
+        let mut child: Child = cmd
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(ExecFailure::spawn)?;
+
+        let stdout: JoinHandle<Result<Vec<u8>, Error>> = match child.stdout.take() {
+            Some(pipe) => thread::spawn(move || tee(pipe, io::stdout())),
+            None => thread::spawn(|| Ok(Vec::new())),
+        };
+        let stderr: JoinHandle<Result<Vec<u8>, Error>> = match child.stderr.take() {
+            Some(pipe) => thread::spawn(move || tee(pipe, io::stderr())),
+            None => thread::spawn(|| Ok(Vec::new())),
+        };
+
+        let join = |handle: JoinHandle<Result<Vec<u8>, Error>>| {
+            handle
+                .join()
+                .unwrap_or_else(|_| Err(Error::other("Output forwarding thread panicked")))
+                .map_err(ExecFailure::wait)
+        };
+        let status: ExitStatus = match child.wait() {
+            Ok(status) => status,
+            Err(e) => {
+                // The threads only finish once the pipes close, so the program is stopped first.
+                let _ = child.kill();
+                let _ = join(stdout);
+                let _ = join(stderr);
+                return Err(ExecFailure::wait(e));
+            }
+        };
+        let duration: Duration = start.elapsed();
+        Ok(ExecOutcome {
+            output: Output {
+                status,
+                stdout: join(stdout)?,
+                stderr: join(stderr)?,
+            },
+            duration,
+        })
+    }
+}
+
+/// The stage at which executing a program failed.
+///
+/// This is carried inside of the [Error]s returned by [`ExecBuilder::exec`]
+/// and [`ExecBuilder::exec_tee`], and can be retrieved with
+/// [of](#method.of). The [Error] keeps the kind of the original error.
+#[derive(Debug)]
+pub enum ExecFailure {
+    /// The program couldn't be started, which usually means that the
+    /// binary is missing, isn't executable, or is built for another platform.
+    Spawn(Error),
+    /// The program was started, but an I/O error occurred while waiting for
+    /// it or reading its output.
+    Wait(Error),
+}
+
+impl ExecFailure {
+    /// Gets the [ExecFailure] carried by an [Error], if there is one.
+    pub fn of(error: &Error) -> Option<&ExecFailure> {
+        error.get_ref()?.downcast_ref::<ExecFailure>()
+    }
+
+    /// Wraps an error that occurred while starting a program.
+    fn spawn(error: Error) -> Error {
+        Error::new(error.kind(), ExecFailure::Spawn(error))
+    }
+
+    /// Wraps an error that occurred after a program was started.
+    fn wait(error: Error) -> Error {
+        Error::new(error.kind(), ExecFailure::Wait(error))
+    }
+}
+
+impl std::fmt::Display for ExecFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecFailure::Spawn(e) => write!(f, "The program could not be started: {}", e),
+            ExecFailure::Wait(e) => {
+                write!(
+                    f,
+                    "The program was started, but waiting for it failed: {}",
+                    e
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExecFailure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExecFailure::Spawn(e) | ExecFailure::Wait(e) => Some(e),
+        }
+    }
+}
+
+/// The outcome of a program executed through an [ExecBuilder].
+#[derive(Debug)]
+pub struct ExecOutcome {
+    /// The exit status of the program, and any output that was captured.
+    pub output: Output,
+    /// The wall-clock time from starting the program until it exited.
+    pub duration: Duration,
+}
+
+/// Reads a stream to its end, writing everything read through to another stream.
+///
+/// The data read is returned, regardless of whether it could be written.
+fn tee(mut reader: impl Read, mut writer: impl Write) -> Result<Vec<u8>, Error> {
+    let mut captured: Vec<u8> = Vec::new();
+    let mut buffer: [u8; 8192] = [0; 8192];
+    let mut forwarding: bool = true;
+    loop {
+        let read: usize = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if forwarding {
+            forwarding = writer
+                .write_all(&buffer[..read])
+                .and_then(|_| writer.flush())
+                .is_ok();
+        }
+        captured.extend_from_slice(&buffer[..read]);
+    }
+    Ok(captured)
+}
+
 /// Executes a specified program under a version directory.
 ///
 /// `libmask` will check ahead of time if the program specified is available as
@@ -266,28 +3066,13 @@ pub fn create_patched_cmd(
 /// typically expect, as an example, the compiler or Haxelib to be available.
 /// Alongside this, all standard `stdio` streams are inherited for live input
 /// and output.
+///
+/// If the `MASK_EXEC_WRAPPER` environment variable is set, the program is
+/// executed through that wrapper; see [`ExecBuilder::wrapper`].
 pub fn haxe_exec(args: Vec<String>, config: Config, prog: Option<String>) -> Result<Output, Error> {
-    match config.0.get_path_installed() {
-        Ok(buf) => {
-            let mut prog_buf: PathBuf = buf.clone();
-
-            prog_buf.push(prog.unwrap_or("haxe".to_string()));
-            if !prog_buf.try_exists()? {
-                Err(Error::new(
-                    ErrorKind::NotFound,
-                    format!(
-                        "Program at file location \"{}\" does not exist",
-                        prog_buf.display()
-                    ),
-                ))
-            } else {
-                create_patched_cmd(args, config, prog_buf)?
-                    .stdin(Stdio::inherit())
-                    .stdout(Stdio::inherit())
-                    .stderr(Stdio::inherit())
-                    .output()
-            }
-        }
-        Err(e) => Err(e),
-    }
+    ExecBuilder::new(config)
+        .prog(prog.as_deref().unwrap_or("haxe"))
+        .args(args)
+        .exec()
+        .map(|outcome| outcome.output)
 }
@@ -8,24 +8,45 @@
 //! [Haxe](https://haxe.org).
 
 use std::{
-    borrow::Cow,
+    collections::BTreeMap,
     env,
-    fs::read_dir,
-    io::Error,
-    path::PathBuf,
-    process::{Stdio, exit},
-    slice::Iter,
+    ffi::OsString,
+    fs::{create_dir_all, read_to_string, write},
+    io::{Error, ErrorKind, IsTerminal, stdout},
+    path::{Path, PathBuf},
+    process::{ExitStatus, exit},
 };
 
 use clap::{Arg, ArgAction, ArgMatches, Command, arg, command};
 
 use libmask::*;
 
+/// The project templates available to the `init` subcommand, as names and `build.hxml` contents.
+const TEMPLATES: [(&str, &str); 3] = [
+    ("js", "-cp src\n-main Main\n-js bin/main.js\n"),
+    ("hashlink", "-cp src\n-main Main\n-hl bin/main.hl\n"),
+    ("neko", "-cp src\n-main Main\n-neko bin/main.n\n"),
+];
+
+/// The `src/Main.hx` file shared by every project template.
+const TEMPLATE_MAIN: &str = "class Main {
+\tstatic function main() {
+\t\ttrace(\"Hello, world!\");
+\t}
+}
+";
+
 /// Give possible commands to [clap].
-fn handle_commands() -> ArgMatches {
+fn handle_commands() -> Command {
     command!()
         .arg(
             arg!(-e --explicit "Use an explicit Haxe version")
+                .long_help(
+                    "Use an explicit Haxe version, which applies to every subcommand \
+                    that uses a Haxe version, including exec, lib and run. It has to \
+                    be passed before the subcommand, since the arguments after exec, \
+                    lib and run are passed to the executed program.",
+                )
                 .action(ArgAction::Set)
                 .value_name("HAXE_VERSION"),
         )
@@ -34,6 +55,52 @@ fn handle_commands() -> ArgMatches {
                 .action(ArgAction::Set)
                 .value_name("CONFIG"),
         )
+        .arg(
+            Arg::new("cwd")
+                .long("cwd")
+                .help("Run as if mask-hx was started in a different directory")
+                .action(ArgAction::Set)
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::new("trace")
+                .long("trace")
+                .help("Prints how the Haxe version and executed programs are resolved")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-inherit-env")
+                .long("no-inherit-env")
+                .help("Executes programs without inheriting the current environment")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("allow-env")
+                .long("allow-env")
+                .value_name("NAME")
+                .help("Executes programs inheriting only the named environment variables")
+                .long_help(
+                    "Executes programs inheriting only the named environment variables. \
+                    Can be given multiple times, or as a comma-separated list.",
+                )
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .conflicts_with("no-inherit-env"),
+        )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .help("Colors the human-readable output")
+                .long_help(
+                    "Colors the human-readable output of list, check, sync, matrix, verify \
+                    and doctor. With auto, colors are only used if the standard output \
+                    is a terminal and the NO_COLOR environment variable isn't set. Output \
+                    meant for scripts, such as --porcelain and --json, is never colored.",
+                )
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto")
+                .value_name("WHEN"),
+        )
         .subcommand(
             Command::new("check")
                 .about("Checks whether or not a Haxe version is installed")
@@ -43,7 +110,48 @@ fn handle_commands() -> ArgMatches {
                     ~/.haxe/ directory, where ~ is the home directory, and checks \
                     if the standard library is present as well.\n\n\
                     If the explicit argument isn't used, then the .mask configuration \
-                    will be read.",
+                    will be read, or the .mask.local configuration if it exists.\n\n\
+                    With the --all flag, every installed Haxe version is checked \
+                    instead, which additionally requires the compiler to be present.\n\n\
+                    The --verify option compares the SHA-256 checksum of the compiler \
+                    against an expected one, which detects a corrupted or modified \
+                    compiler.\n\n\
+                    The exit code is 2 if any of the checked versions is broken. By \
+                    default, --all checks every version before summarizing them; with \
+                    --fail-fast, it stops at the first broken version instead, so only \
+                    the versions checked up to that point are listed.",
+                )
+                .arg(
+                    Arg::new("all")
+                        .short('a')
+                        .long("all")
+                        .help("Checks every installed Haxe version")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--verify "Verifies the compiler against an expected SHA-256 checksum")
+                        .action(ArgAction::Set)
+                        .value_name("SHA256")
+                        .conflicts_with("all"),
+                )
+                .arg(
+                    arg!(--"fail-fast" "Stops --all at the first broken version")
+                        .action(ArgAction::SetTrue)
+                        .requires("all"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Prints the results of --all as a JSON object")
+                        .action(ArgAction::SetTrue)
+                        .requires("all"),
+                )
+                .arg(
+                    Arg::new("porcelain")
+                        .long("porcelain")
+                        .help("Prints the results in a stable format for scripts")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("json"),
                 ),
         )
         .subcommand(
@@ -51,7 +159,68 @@ fn handle_commands() -> ArgMatches {
                 .about("List all of the installed Haxe versions")
                 .long_about(
                     "This lists every Haxe version available in the Haxe \
-                    versions directory.",
+                    versions directory.\n\n\
+                    With the --porcelain flag, each line instead contains the \
+                    version, whether it's the active version, and whether it's \
+                    complete, in a format that is stable across releases. Aliases \
+                    aren't included in it.\n\n\
+                    Otherwise, aliases are marked as such, and the --resolve-aliases \
+                    flag shows what each symlinked entry points at instead.\n\n\
+                    Versions are listed from newest to oldest. The --filter option only \
+                    lists the versions whose names match a pattern: a pattern with * \
+                    or ? is a glob that has to match the whole name, such as 4.3.*, \
+                    and any other pattern matches names that contain it. The --limit \
+                    option then lists at most the given number of the newest versions \
+                    that are left.",
+                )
+                .arg(
+                    Arg::new("porcelain")
+                        .long("porcelain")
+                        .help("Prints the versions in a stable format for scripts")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("resolve-aliases")
+                        .long("resolve-aliases")
+                        .help("Shows the target of every alias")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("porcelain"),
+                )
+                .arg(
+                    arg!(--filter "Only lists the versions whose names match a pattern")
+                        .action(ArgAction::Set)
+                        .value_name("PATTERN"),
+                )
+                .arg(
+                    arg!(--limit "Only lists a number of the newest versions")
+                        .action(ArgAction::Set)
+                        .value_parser(clap::value_parser!(usize))
+                        .value_name("N"),
+                ),
+        )
+        .subcommand(
+            Command::new("std-path")
+                .about("Prints the standard library path of a Haxe version")
+                .long_about(
+                    "This prints the absolute path to the standard library of the \
+                    configured Haxe version, which is useful for build tools that \
+                    need to pass it as a class path. The Haxe version has to be \
+                    installed for this to succeed.",
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Prints the result as a JSON object")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("env")
+                .about("Prints the environment used for a Haxe version")
+                .long_about(
+                    "This prints the environment variables that the execution \
+                    subcommands set for the configured Haxe version, one KEY=VALUE \
+                    pair per line.",
                 ),
         )
         .subcommand(
@@ -60,26 +229,205 @@ fn handle_commands() -> ArgMatches {
                 .long_about(
                     "This initially checks the validity of a Haxe installation, \
                     and then switches the configuration to use that specified Haxe \
-                    version.",
+                    version.\n\n\
+                    With the --check-only flag, the same checks are performed, along \
+                    with whether the configuration file can be written, but nothing \
+                    is changed. The exit code is 1 if the switch would fail.",
                 )
                 .arg(arg!(<HAXE_VERSION> "The Haxe version to switch to"))
+                .arg(
+                    Arg::new("local")
+                        .short('l')
+                        .long("local")
+                        .help("Switches the local .mask.local configuration instead")
+                        .action(ArgAction::SetTrue),
+                )
                 .arg(
                     Arg::new("skip-check")
                         .short('u')
                         .long("skip-check")
                         .help("Skips checking the existence of a Haxe installation")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("check-only")
+                        .long("check-only")
+                        .help("Reports whether the switch would succeed, without changing anything")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("init")
+                .about("Creates a configuration for a new project")
+                .long_about(
+                    "This creates a configuration that uses the currently configured \
+                    Haxe version, or the newest installed one if there is none.\n\n\
+                    The --template option additionally creates a build.hxml file and \
+                    a src/Main.hx file for a target, which gives a project that can be \
+                    built right away. Passing list to it lists the available templates. \
+                    Existing files are never overwritten.",
+                )
+                .arg(
+                    arg!(--template "Creates a minimal project for a target")
+                        .action(ArgAction::Set)
+                        .value_name("NAME"),
                 ),
         )
+        .subcommand(
+            Command::new("config")
+                .about("Manages configuration files")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("init")
+                        .about("Writes a configuration file for the current project")
+                        .long_about(
+                            "This writes a configuration file that uses the currently \
+                            configured Haxe version, or the newest installed one if there \
+                            is none. An existing configuration file is only overwritten \
+                            with the --force flag.",
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .short('f')
+                                .long("force")
+                                .help("Overwrites an existing configuration file")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("upgrade")
+                .about("Moves the configuration to the next installed Haxe version")
+                .long_about(
+                    "This reads the Haxe version from the configuration, and switches \
+                    the configuration to the next newer Haxe version that is installed. \
+                    Nothing is changed if the configuration already uses the newest \
+                    installed version. The file that is rewritten is the one the \
+                    configuration was read from, such as the closest .mask file.\n\n\
+                    The --to option can be used to upgrade to a specific installed \
+                    Haxe version instead, which has to be newer than the current one.",
+                )
+                .arg(
+                    arg!(--to "Upgrade to a specific installed Haxe version")
+                        .action(ArgAction::Set)
+                        .value_name("HAXE_VERSION"),
+                ),
+        )
+        .subcommand(
+            Command::new("pack")
+                .about("Packages a Haxe version into a tarball")
+                .long_about(
+                    "This packages an installed Haxe version into a gzip-compressed \
+                    tarball, which is useful for transferring a known-good version to \
+                    machines without network access. Extracting the tarball into the \
+                    Haxe versions directory installs the version again.",
+                )
+                .arg(arg!(<HAXE_VERSION> "The Haxe version to package"))
+                .arg(arg!(<OUTPUT> "The path to write the tarball to")),
+        )
+        .subcommand(
+            Command::new("copy")
+                .about("Copies a Haxe version under a new name")
+                .long_about(
+                    "This copies an installed Haxe version to a new version in the Haxe \
+                    versions directory, which is useful for experimenting with a patched \
+                    toolchain without changing the original. An existing version is \
+                    never overwritten.",
+                )
+                .arg(arg!(<HAXE_VERSION> "The Haxe version to copy"))
+                .arg(arg!(<NAME> "The name of the new Haxe version")),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about(
+                    "Checks that every configuration in a directory tree uses an installed version",
+                )
+                .long_about(
+                    "This finds every .mask and .mask.local file in a directory and all \
+                    of its subdirectories, resolves the Haxe version of each one, and \
+                    lists the files whose Haxe version isn't installed or can't be \
+                    resolved. Files that are blank or contain inherit are skipped. It's \
+                    meant as a pre-commit or CI check, and the exit code is 2 if any \
+                    file is broken.",
+                )
+                .arg(arg!([DIR] "The directory to search").default_value(".")),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Diagnoses problems with the setup of mask-hx")
+                .long_about(
+                    "This runs a series of checks on the installations directory, the \
+                    configuration, the configured Haxe version and its standard \
+                    library, and prints the status of each one. Checks that can't \
+                    run because an earlier one didn't pass are reported as warnings.\n\n\
+                    With --json, the results are printed as a JSON object instead, \
+                    with a checks array of objects holding the check, status and \
+                    detail, and an overall ok boolean. Either way, the exit code is 2 \
+                    if any check failed.",
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Prints the results as a JSON object")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("lock")
+                .about("Records the installed Haxe versions in a lock file")
+                .long_about(
+                    "This writes every installed Haxe version, alongside the SHA-256 \
+                    checksum of its compiler, to a lock file, with one version per \
+                    line. Sharing the lock file lets a team check that everyone has \
+                    the same set of Haxe versions using the sync subcommand. \
+                    Versions without a compiler are skipped.",
+                )
+                .arg(arg!([FILE] "The lock file to write").default_value("versions.lock")),
+        )
+        .subcommand(
+            Command::new("sync")
+                .about("Compares the installed Haxe versions against a lock file")
+                .long_about(
+                    "This reads a lock file written by the lock subcommand, and reports \
+                    every Haxe version in it that is missing, or whose compiler doesn't \
+                    match the recorded checksum. Since Haxe versions aren't downloaded \
+                    automatically, missing versions have to be placed in the Haxe \
+                    versions directory by hand. Every version is checked, and the exit \
+                    code is 2 if any of them don't match.",
+                )
+                .arg(arg!([FILE] "The lock file to read").default_value("versions.lock")),
+        )
         .subcommand(
             Command::new("exec")
                 .about("Executes the Haxe compiler")
                 .long_about(
                     "This checks for the existence of the Haxe compiler, and then \
                     executes it. The Haxe compiler used is the one provided by the \
-                    currently configured version.",
+                    currently configured version.\n\n\
+                    The --compiler option can be used to run a different compiler \
+                    binary instead, such as a locally-built one. The environment \
+                    of the configured version is still used for it.\n\n\
+                    The --dump-env option writes the environment the compiler is \
+                    launched with to a file before running it, as KEY=VALUE lines. \
+                    Values are written as-is, so the file may contain secrets \
+                    from the environment.\n\n\
+                    If $MASK_EXEC_ARGS is set, it is split into arguments that are \
+                    passed before the given ones. Whitespace separates arguments, \
+                    single quotes keep their contents as-is, double quotes allow \
+                    \\\" and \\\\ escapes, and a backslash escapes the next character \
+                    elsewhere.",
                 )
                 .disable_help_flag(true)
+                .arg(
+                    arg!(--compiler "Run a specific compiler binary instead")
+                        .action(ArgAction::Set)
+                        .value_name("PATH"),
+                )
+                .arg(
+                    arg!(--"dump-env" "Write the environment of the compiler to a file")
+                        .action(ArgAction::Set)
+                        .value_name("FILE"),
+                )
                 .arg(
                     arg!([ARGUMENTS]... "Specify the arguments to pass to the compiler")
                         .value_delimiter(' ')
@@ -87,14 +435,31 @@ fn handle_commands() -> ArgMatches {
                         .trailing_var_arg(true),
                 ),
         )
+        .subcommand(
+            Command::new("build")
+                .about("Builds the project using a .hxml file")
+                .long_about(
+                    "This executes the Haxe compiler of the currently configured \
+                    version with a .hxml file, which defaults to build.hxml in the \
+                    working directory. The arguments in $MASK_EXEC_ARGS are passed \
+                    before it, the same way as with exec.",
+                )
+                .arg(arg!([HXML] "The .hxml file to build").default_value("build.hxml")),
+        )
         .subcommand(
             Command::new("lib")
                 .about("Executes Haxelib")
                 .long_about(
                     "This acts similar to the exec subcommand, but instead performs \
-                    operations on Haxelib, the Haxe package manager.",
+                    operations on Haxelib, the Haxe package manager.\n\n\
+                    If a .haxelib directory exists in the working directory or one \
+                    of its parents, the closest one is used as the repository through \
+                    the HAXELIB_PATH environment variable, so that a project's \
+                    libraries can be committed alongside its .mask file. The --global \
+                    flag uses the global repository instead.",
                 )
                 .disable_help_flag(true)
+                .arg(arg!(--global "Uses the global Haxelib repository").action(ArgAction::SetTrue))
                 .arg(
                     arg!([ARGUMENTS]... "Specify the arguments to pass to Haxelib")
                         .value_delimiter(' ')
@@ -102,6 +467,59 @@ fn handle_commands() -> ArgMatches {
                         .trailing_var_arg(true),
                 ),
         )
+        .subcommand(
+            Command::new("matrix")
+                .about("Runs the compiler or Haxelib with several Haxe versions")
+                .long_about(
+                    "This runs the exec or lib subcommand once for each of the given \
+                    Haxe versions, in order, using the environment of each version. \
+                    A summary of which versions passed is printed at the end, and \
+                    the exit code is non-zero if any of them failed.\n\n\
+                    The --all flag uses every installed Haxe version instead. By \
+                    default, the remaining versions are skipped after the first \
+                    failure, unless --keep-going is passed.",
+                )
+                .subcommand_required(true)
+                .subcommand_precedence_over_arg(true)
+                .arg(
+                    arg!([VERSIONS]... "The Haxe versions to run with")
+                        .required_unless_present("all"),
+                )
+                .arg(
+                    Arg::new("all")
+                        .short('a')
+                        .long("all")
+                        .help("Runs with every installed Haxe version")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("VERSIONS"),
+                )
+                .arg(
+                    arg!(--"keep-going" "Continues with the remaining versions after a failure")
+                        .action(ArgAction::SetTrue),
+                )
+                .subcommand(
+                    Command::new("exec")
+                        .about("Executes the Haxe compiler")
+                        .disable_help_flag(true)
+                        .arg(
+                            arg!([ARGUMENTS]... "Specify the arguments to pass to the compiler")
+                                .value_delimiter(' ')
+                                .allow_hyphen_values(true)
+                                .trailing_var_arg(true),
+                        ),
+                )
+                .subcommand(
+                    Command::new("lib")
+                        .about("Executes Haxelib")
+                        .disable_help_flag(true)
+                        .arg(
+                            arg!([ARGUMENTS]... "Specify the arguments to pass to Haxelib")
+                                .value_delimiter(' ')
+                                .allow_hyphen_values(true)
+                                .trailing_var_arg(true),
+                        ),
+                ),
+        )
         .subcommand(
             Command::new("run")
                 .about("Execute a program using an isolated Haxe version")
@@ -112,7 +530,9 @@ fn handle_commands() -> ArgMatches {
                     the executed program becomes informed of being within a running mask-hx \
                     context through the $MASK_PATH_OVERRIDE environment variable that's \
                     set to the current Haxe version. This is primarily useful for prompts \
-                    that can display metadata about the program's current state.",
+                    that can display metadata about the program's current state.\n\n\
+                    Running neko uses the Neko bundled with the Haxe version, if it \
+                    ships one.",
                 )
                 .disable_help_flag(true)
                 .arg(arg!(<PROGRAM> "The program to execute"))
@@ -123,239 +543,1992 @@ fn handle_commands() -> ArgMatches {
                         .trailing_var_arg(true),
                 ),
         )
-        .get_matches()
 }
 
-/// Simple macro that constructs a configuration file from a path.
-macro_rules! config_from_path {
-    ( $path: expr ) => {
-        match Config::new(Some($path)) {
-            Ok(data) => Some(data),
-            Err(_) => None,
-        }
+/// The colors used to highlight the human-readable output.
+#[derive(Clone, Copy)]
+enum Color {
+    /// Used for installed, active and passing versions.
+    Green,
+    /// Used for broken, missing and failing versions.
+    Red,
+    /// Used for versions that were skipped.
+    Yellow,
+}
+
+/// Decides whether the human-readable output is colored, based on the --color flag.
+///
+/// Following the `NO_COLOR` convention, any non-empty value of the variable
+/// disables colors, unless they're forced with `--color always`.
+fn color_enabled(matches: &ArgMatches) -> bool {
+    match matches.get_one::<String>("color").map(String::as_str) {
+        Some("always") => true,
+        Some("never") => false,
+        _ => stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+    }
+}
+
+/// Wraps text in the escape codes of a color, or returns it as-is if colors are disabled.
+fn paint(text: &str, color: Color, enabled: bool) -> String {
+    if !enabled {
+        return text.to_string();
+    }
+    let code: u8 = match color {
+        Color::Green => 32,
+        Color::Red => 31,
+        Color::Yellow => 33,
     };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
 }
 
-/// The entry point of the program.
+/// Quotes and escapes a string so that it can be used as a JSON string.
+fn json_string(data: &str) -> String {
+    let mut quoted: String = String::with_capacity(data.len() + 2);
+    quoted.push('"');
+    for c in data.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Explains how to make a Haxe version available if it isn't installed.
 ///
-/// This handles the arguments, as well as how the program should exit.
-fn main() {
-    let matches: ArgMatches = handle_commands();
-    let mut message: Box<String> = Box::new(
-        "Invalid subcommand or no subcommand was passed; try running mask-hx help".to_string(),
-    );
-    let mut config_path: Option<Cow<str>> = None;
-    let mut exit_code: i32 = 1;
-    let mut force_exit_log: bool = false;
-
-    let config: Option<Config> = if let Some(version) = matches.get_one::<String>("explicit") {
-        Some(Config(HaxeVersion(version.clone())))
-    } else if let Ok(data) = env::var("MASK_VERSION") {
-        Some(Config(HaxeVersion(data)))
-    } else if let Some(config) = matches.get_one::<String>("config") {
-        config_path = Some(Cow::from(config));
-        config_from_path!(config)
-    } else {
-        match &env::var("MASK_CONFIG") {
-            Ok(config) => {
-                config_path = Some(Cow::from(config.clone()));
-                config_from_path!(config.as_str())
-            }
-            _ => Config::new(None).ok(),
+/// [`None`] is returned if the version is installed, or if its installation
+/// couldn't be inspected, in which case the execution itself reports the error.
+fn missing_version_message(version: &HaxeVersion) -> Option<String> {
+    match version.get_path_installed() {
+        Err(e) if e.kind() == ErrorKind::NotFound => match version.get_path() {
+            Ok(_) => Some(format!(
+                "Haxe version {} is not installed; place a portable copy of it in \"{}\", \
+                or run mask-hx list to see the installed Haxe versions",
+                version.0,
+                version.disk_path_display()
+            )),
+            Err(_) => Some(e.to_string()),
+        },
+        _ => None,
+    }
+}
+
+/// The result of a subcommand, which [main] reports before exiting with its code.
+///
+/// Besides the actual output of the subcommand, such as the list of versions,
+/// this keeps the status lines about it, which are prefixed with `mask-hx: `
+/// when printed.
+struct CommandOutcome {
+    /// The actual output for the standard output, printed as-is before anything else.
+    output: Vec<String>,
+    /// Status lines for the standard output, such as the files that were created.
+    notes: Vec<String>,
+    /// Problems for the standard error that didn't stop the subcommand.
+    warnings: Vec<String>,
+    /// A final message for the standard output, used for successes worth reporting.
+    stdout: Option<String>,
+    /// A final message for the standard error, used for failures.
+    stderr: Option<String>,
+    /// The code that the program exits with.
+    code: i32,
+}
+
+impl CommandOutcome {
+    /// A success with nothing left to report.
+    fn quiet() -> CommandOutcome {
+        CommandOutcome {
+            output: Vec::new(),
+            notes: Vec::new(),
+            warnings: Vec::new(),
+            stdout: None,
+            stderr: None,
+            code: 0,
+        }
+    }
+
+    /// A success that reports a message.
+    fn success(message: String) -> CommandOutcome {
+        CommandOutcome {
+            stdout: Some(message),
+            ..CommandOutcome::quiet()
+        }
+    }
+
+    /// A failure that reports a message, and exits with the given code.
+    fn failure(message: String, code: i32) -> CommandOutcome {
+        CommandOutcome {
+            stderr: Some(message),
+            code,
+            ..CommandOutcome::quiet()
+        }
+    }
+
+    /// Adds lines of actual output, which are printed before any messages.
+    fn with_output(mut self, output: Vec<String>) -> CommandOutcome {
+        self.output.extend(output);
+        self
+    }
+
+    /// Adds status lines that are reported before the final message.
+    fn with_notes(mut self, notes: Vec<String>) -> CommandOutcome {
+        self.notes.extend(notes);
+        self
+    }
+
+    /// Adds problems that are reported before the final message.
+    fn with_warnings(mut self, warnings: Vec<String>) -> CommandOutcome {
+        self.warnings.extend(warnings);
+        self
+    }
+
+    /// The outcome of running a program, whose message is only reported if
+    /// the program exited with a code other than 0.
+    fn exited(message: String, code: i32) -> CommandOutcome {
+        if code == 0 {
+            CommandOutcome::quiet()
+        } else {
+            CommandOutcome::failure(message, code)
         }
+    }
+
+    /// Prints the messages of the outcome, and exits with its code.
+    fn finish(self) -> ! {
+        for line in self.output {
+            println!("{}", line);
+        }
+        for note in self.notes {
+            println!("mask-hx: {}", note);
+        }
+        for warning in self.warnings {
+            eprintln!("mask-hx: {}", warning);
+        }
+        if let Some(message) = self.stdout {
+            println!("mask-hx: {}", message);
+        }
+        if let Some(message) = self.stderr {
+            eprintln!("mask-hx: {}", message);
+        }
+        exit(self.code);
+    }
+}
+
+/// How a [DoctorCheck] turned out.
+#[derive(Clone, Copy, PartialEq)]
+enum CheckStatus {
+    /// The check passed.
+    Ok,
+    /// Something is off, or the check couldn't run, but nothing is broken.
+    Warning,
+    /// Something is broken, which makes the doctor subcommand fail.
+    Failure,
+}
+
+impl CheckStatus {
+    /// The name of the status, as printed by the doctor subcommand.
+    fn name(self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warning",
+            CheckStatus::Failure => "failure",
+        }
+    }
+}
+
+/// The result of a single check of the doctor subcommand.
+///
+/// Both the human-readable and the JSON output are printed from these, so
+/// they always agree.
+struct DoctorCheck {
+    /// The name of the check.
+    check: &'static str,
+    /// How the check turned out.
+    status: CheckStatus,
+    /// What was found, or what went wrong.
+    detail: String,
+}
+
+/// Checks that the installations directory can be read, and holds Haxe versions.
+fn check_installations() -> DoctorCheck {
+    let (status, detail): (CheckStatus, String) = match HaxeVersion::get_haxe_installations()
+        .and_then(|dir| {
+            let versions: Vec<HaxeVersion> = HaxeVersion::list_installed()?;
+            Ok((dir, versions))
+        }) {
+        Ok((dir, versions)) if versions.is_empty() => (
+            CheckStatus::Warning,
+            format!(
+                "No Haxe versions are installed in \"{}\"",
+                display_path(&dir)
+            ),
+        ),
+        Ok((dir, versions)) => (
+            CheckStatus::Ok,
+            format!(
+                "{} Haxe versions are installed in \"{}\"",
+                versions.len(),
+                display_path(&dir)
+            ),
+        ),
+        Err(e) => (CheckStatus::Failure, e.to_string()),
+    };
+    DoctorCheck {
+        check: "installations",
+        status,
+        detail,
+    }
+}
+
+/// Checks that a configuration was found, and that it specifies a Haxe version.
+fn check_config(config: Option<&Config>, error: Option<&Error>) -> DoctorCheck {
+    let (status, detail): (CheckStatus, String) = match (config, error) {
+        (Some(config), _) if !config.is_empty() => (
+            CheckStatus::Ok,
+            format!("Haxe version {} is configured", config.0.0),
+        ),
+        (Some(_), _) => (
+            CheckStatus::Warning,
+            "No Haxe version specified".to_string(),
+        ),
+        (None, Some(e)) => (CheckStatus::Failure, e.to_string()),
+        (None, None) => (
+            CheckStatus::Warning,
+            "No configuration found; create one using the switch subcommand".to_string(),
+        ),
     };
+    DoctorCheck {
+        check: "config",
+        status,
+        detail,
+    }
+}
+
+/// Checks that the configured Haxe version is installed, including its compiler.
+fn check_version(version: Option<&HaxeVersion>) -> DoctorCheck {
+    let (status, detail): (CheckStatus, String) = match version {
+        Some(version) => match missing_version_message(version) {
+            Some(missing) => (CheckStatus::Failure, missing),
+            None => match version.is_complete() {
+                Ok(true) => (
+                    CheckStatus::Ok,
+                    format!(
+                        "Haxe version {} is installed in \"{}\"",
+                        version.0,
+                        version.disk_path_display()
+                    ),
+                ),
+                Ok(false) => (
+                    CheckStatus::Failure,
+                    format!("Haxe version {} has no compiler", version.0),
+                ),
+                Err(e) => (CheckStatus::Failure, e.to_string()),
+            },
+        },
+        None => (
+            CheckStatus::Warning,
+            "Skipped, since no Haxe version is configured".to_string(),
+        ),
+    };
+    DoctorCheck {
+        check: "version",
+        status,
+        detail,
+    }
+}
 
-    /// Parses an [ArgMatches] for the `ARGUMENTS` argument, and returns it.
-    macro_rules! parse_args {
-        ( $x: expr ) => {{
-            let mut args: Vec<String> = Vec::new();
-            if let Some(list) = $x.get_many::<String>("ARGUMENTS") {
-                for i in list {
-                    args.push(i.to_string());
+/// Checks that the standard library of the configured Haxe version can be found.
+fn check_std(version: Option<&HaxeVersion>) -> DoctorCheck {
+    let (status, detail): (CheckStatus, String) = match version.map(|v| v.locate_std_path()) {
+        Some(Ok((path, source))) if path.is_dir() => (
+            CheckStatus::Ok,
+            match source {
+                StdSource::Environment => {
+                    format!("Found at \"{}\" from HAXE_STD_PATH", display_path(&path))
                 }
+                _ => format!("Found at \"{}\"", display_path(&path)),
+            },
+        ),
+        Some(Ok((path, _))) => (
+            CheckStatus::Failure,
+            format!("Not found at \"{}\"", display_path(&path)),
+        ),
+        Some(Err(e)) => (CheckStatus::Failure, e.to_string()),
+        None => (
+            CheckStatus::Warning,
+            "Skipped, since no Haxe version is configured".to_string(),
+        ),
+    };
+    DoctorCheck {
+        check: "std",
+        status,
+        detail,
+    }
+}
+
+/// Parses an [ArgMatches] for the `ARGUMENTS` argument, and returns it.
+macro_rules! parse_args {
+    ( $x: expr ) => {{
+        let mut args: Vec<String> = Vec::new();
+        if let Some(list) = $x.get_many::<String>("ARGUMENTS") {
+            for i in list {
+                args.push(i.to_string());
             }
-            args
-        }};
+        }
+        args
+    }};
+}
+
+/// Generates a basic execution message.
+macro_rules! exec_message {
+    ( $x: expr, $y: expr ) => {
+        if $x.is_none() {
+            format!("Successfully started {}, but program was interrupted", $y)
+        } else {
+            format!("Successfully started {}, but program returned error", $y)
+        }
+    };
+}
+
+/// Gets the configuration of a [Context], or returns the failure explaining
+/// why there is none from the subcommand.
+macro_rules! required_config {
+    ( $x: expr ) => {
+        match $x.required_config() {
+            Ok(config) => config,
+            Err(outcome) => return *outcome,
+        }
+    };
+}
+
+/// The state that subcommands run with, gathered from the global arguments.
+struct Context<'a> {
+    /// The global arguments.
+    matches: &'a ArgMatches,
+    /// The resolved configuration, if one was found.
+    config: Option<Config>,
+    /// The error that prevented the configuration from being resolved, if any.
+    config_error: Option<Error>,
+    /// The configuration file named by --config or the environment, if any.
+    config_path: Option<String>,
+    /// The configuration file that the configuration was read from, if any.
+    config_file: Option<PathBuf>,
+    /// Whether the resolution of versions and programs is printed.
+    trace: bool,
+    /// Whether the human-readable output is colored.
+    color: bool,
+}
+
+impl Context<'_> {
+    /// Checks the validity of the configuration, and returns it, or the failure to finish with if it is invalid.
+    ///
+    /// Every subcommand that needs a Haxe version gets it through this, so
+    /// that every version reference method, such as the --explicit flag,
+    /// applies to all of them in the same way. A configuration file that
+    /// exists but couldn't be read is reported with the exit code 1, rather
+    /// than being treated as a missing configuration.
+    fn required_config(&self) -> Result<Config, Box<CommandOutcome>> {
+        match (&self.config, &self.config_error) {
+            (Some(data), _) if !data.is_empty() => Ok(data.clone()),
+            (Some(_), _) => Err(Box::new(CommandOutcome::failure(
+                "No Haxe version specified".to_string(),
+                2,
+            ))),
+            (None, Some(e)) => Err(Box::new(CommandOutcome::failure(e.to_string(), 1))),
+            (None, None) => Err(Box::new(CommandOutcome::failure(
+                "Impossible to construct valid configuration; \
+                for starters, use the --explicit flag to specify the version, \
+                or create a configuration file using the switch subcommand"
+                    .to_string(),
+                2,
+            ))),
+        }
+    }
+
+    /// Creates an [ExecBuilder] that respects the global execution flags.
+    fn exec_builder(&self, config: Config) -> ExecBuilder {
+        let builder: ExecBuilder =
+            ExecBuilder::new(config).inherit_env(!self.matches.get_flag("no-inherit-env"));
+        match self.matches.get_many::<String>("allow-env") {
+            Some(names) => builder.env_allowlist(&names.map(String::as_str).collect::<Vec<_>>()),
+            None => builder,
+        }
+    }
+
+    /// Whether executed programs inherit the whole environment of `mask-hx`.
+    fn inherits_env(&self) -> bool {
+        !self.matches.get_flag("no-inherit-env") && !self.matches.contains_id("allow-env")
     }
+}
 
-    /// Generates a basic execution message.
-    macro_rules! exec_message {
-        ( $x: expr, $y: expr ) => {
-            if $x.is_none() {
-                format!("Successfully started {}, but program was interrupted", $y)
-            } else {
-                format!("Successfully started {}, but program returned error", $y)
+/// Runs the program that an [ExecBuilder] is set up for, with all standard `stdio` streams inherited.
+///
+/// If tracing is enabled, the execution plan is printed beforehand, and the
+/// time the program took afterwards.
+fn run_cmd(builder: ExecBuilder, trace: bool) -> Result<ExitStatus, Error> {
+    if trace {
+        let cmd: std::process::Command = builder.clone().build()?;
+        eprintln!(
+            "mask-hx: trace: program: {}",
+            cmd.get_program().to_string_lossy()
+        );
+        for arg in cmd.get_args() {
+            eprintln!("mask-hx: trace: argument: {}", arg.to_string_lossy());
+        }
+        for (key, value) in cmd.get_envs() {
+            match value {
+                Some(value) => eprintln!(
+                    "mask-hx: trace: env: {}={}",
+                    key.to_string_lossy(),
+                    value.to_string_lossy()
+                ),
+                None => eprintln!("mask-hx: trace: env: {} (removed)", key.to_string_lossy()),
             }
+        }
+    }
+    let outcome: ExecOutcome = builder.exec()?;
+    if trace {
+        eprintln!("mask-hx: trace: finished in {:?}", outcome.duration);
+    }
+    Ok(outcome.output.status)
+}
+
+/// Explains why a program couldn't be run, telling apart a program that
+/// couldn't be started from one that couldn't be waited for.
+fn exec_error(prog: &str, e: &Error) -> String {
+    match ExecFailure::of(e) {
+        Some(ExecFailure::Spawn(cause)) => {
+            format!("\"{}\" could not be started: {}", prog, cause)
+        }
+        Some(ExecFailure::Wait(cause)) => format!(
+            "\"{}\" was started, but waiting for it failed: {}",
+            prog, cause
+        ),
+        None => format!("Execution error: {}", e),
+    }
+}
+
+/// Writes the environment a command will be launched with to a file, one
+/// `KEY=VALUE` pair per line.
+///
+/// The environment of `mask-hx` itself is only included when `inherit_env`
+/// is set, since the command otherwise starts from an empty environment.
+fn dump_env(cmd: &std::process::Command, inherit_env: bool, file: &str) -> Result<(), Error> {
+    let mut vars: BTreeMap<OsString, OsString> = if inherit_env {
+        env::vars_os().collect()
+    } else {
+        BTreeMap::new()
+    };
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => vars.insert(key.to_os_string(), value.to_os_string()),
+            None => vars.remove(key),
         };
     }
 
-    /// Shorthand method for executing a program.
-    fn execute(params: &ArgMatches, config: Config, prog: &str) -> Result<(String, i32), Error> {
-        let args: Vec<String> = parse_args!(params);
+    let mut contents: String = String::new();
+    for (key, value) in vars {
+        contents.push_str(&format!(
+            "{}={}\n",
+            key.to_string_lossy(),
+            value.to_string_lossy()
+        ));
+    }
+    write(file, contents).map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("Could not write environment to \"{}\": {}", file, e),
+        )
+    })
+}
 
-        match haxe_exec(args, config, Some(prog.to_string())) {
-            Ok(output) => Ok((
-                exec_message!(output.status.code(), prog),
-                output.status.code().unwrap_or(143),
-            )),
-            Err(e) => Err(e),
+/// Shorthand method for executing a program that the builder is already set up for.
+///
+/// When `dump` is given, it holds the file that the environment of the
+/// program is written to before it runs, and whether that environment
+/// includes the one of `mask-hx`.
+fn execute(
+    params: &ArgMatches,
+    builder: ExecBuilder,
+    prog: &str,
+    trace: bool,
+    dump: Option<(&str, bool)>,
+) -> Result<CommandOutcome, Error> {
+    let builder: ExecBuilder = builder.args(injected_args(parse_args!(params))?);
+    if let Some((file, inherit_env)) = dump {
+        dump_env(&builder.clone().build()?, inherit_env, file)?;
+    }
+
+    run_cmd(builder, trace).map(|status| {
+        CommandOutcome::exited(
+            exec_message!(status.code(), prog),
+            status.code().unwrap_or(143),
+        )
+    })
+}
+
+/// Prepends the arguments from $MASK_EXEC_ARGS to the ones given to the compiler by exec and build.
+///
+/// The variable is split with [split_args], and is ignored when it is
+/// unset or empty.
+fn injected_args(args: Vec<String>) -> Result<Vec<String>, Error> {
+    match env::var("MASK_EXEC_ARGS") {
+        Ok(extra) if !extra.is_empty() => {
+            let mut injected: Vec<String> = split_args(&extra)
+                .map_err(|e| Error::new(e.kind(), format!("Invalid MASK_EXEC_ARGS: {}", e)))?;
+            injected.extend(args);
+            Ok(injected)
         }
+        _ => Ok(args),
     }
+}
 
-    /// Checks the validity of a configuration, and exits if it is invalid.
-    fn check_config_validity(config: &Option<Config>) {
-        if let Some(data) = config {
-            if data.0.0.is_empty() {
-                eprintln!("mask-hx: No Haxe version specified");
-            } else {
-                return;
-            }
-        } else {
-            eprintln!(
-                "mask-hx: Impossible to construct valid configuration; \
-                for starters, use the --explicit flag to specify the version, \
-                or create a configuration file using the switch subcommand"
-            );
+/// Applies the --filter and --limit options of the list subcommand to the
+/// versions it lists, which are expected to be sorted from newest to oldest.
+fn narrow_list<T>(
+    params: &ArgMatches,
+    entries: Vec<T>,
+    version_of: impl Fn(&T) -> HaxeVersion,
+) -> Vec<T> {
+    let mut entries: Vec<T> = match params.get_one::<String>("filter") {
+        Some(pattern) => entries
+            .into_iter()
+            .filter(|entry| version_of(entry).name_matches(pattern))
+            .collect(),
+        None => entries,
+    };
+    if let Some(limit) = params.get_one::<usize>("limit") {
+        entries.truncate(*limit);
+    }
+    entries
+}
+
+/// Picks the Haxe version that new configurations use, which is the
+/// configured one, or the newest installed one if there is none.
+fn initial_version(config: &Option<Config>) -> Result<HaxeVersion, Error> {
+    match config {
+        Some(data) if !data.is_empty() => Ok(data.0.clone()),
+        _ => HaxeVersion::installed_sorted().and_then(|versions| {
+            versions.into_iter().next().ok_or(Error::new(
+                ErrorKind::NotFound,
+                "No Haxe versions are installed",
+            ))
+        }),
+    }
+}
+
+/// Reports the progress of a subcommand that runs several programs.
+///
+/// Unlike the messages of a [CommandOutcome], these can't wait for the
+/// subcommand to finish, since they tell apart the output of each program.
+fn progress(message: &str) {
+    eprintln!("mask-hx: {}", message);
+}
+
+/// Checks every installed Haxe version, as done by check --all.
+fn check_all_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let versions: Vec<HaxeVersion> = match HaxeVersion::installed_sorted() {
+        Ok(versions) => versions,
+        Err(e) => return CommandOutcome::failure(e.to_string(), 2),
+    };
+    let mut results: Vec<(HaxeVersion, bool)> = Vec::with_capacity(versions.len());
+    for version in versions {
+        let complete: bool = version.is_complete().unwrap_or(false);
+        results.push((version, complete));
+        if !complete && params.get_flag("fail-fast") {
+            break;
         }
-        exit(2);
     }
+    let broken: usize = results.iter().filter(|(_, complete)| !complete).count();
+    let healthy: usize = results.len() - broken;
+
+    let output: Vec<String> = if params.get_flag("json") {
+        let entries: Vec<String> = results
+            .iter()
+            .map(|(version, complete)| {
+                format!(
+                    "{{\"version\":{},\"complete\":{}}}",
+                    json_string(&version.0),
+                    complete
+                )
+            })
+            .collect();
+        vec![format!(
+            "{{\"versions\":[{}],\"healthy\":{},\"broken\":{}}}",
+            entries.join(","),
+            healthy,
+            broken
+        )]
+    } else if params.get_flag("porcelain") {
+        results
+            .iter()
+            .map(|(version, complete)| {
+                format!(
+                    "{} {}",
+                    version.0,
+                    if *complete { "installed" } else { "broken" }
+                )
+            })
+            .collect()
+    } else {
+        results
+            .iter()
+            .map(|(version, complete)| {
+                format!(
+                    "[{}] {}",
+                    if *complete {
+                        paint("ok", Color::Green, ctx.color)
+                    } else {
+                        paint("broken", Color::Red, ctx.color)
+                    },
+                    version.0
+                )
+            })
+            .collect()
+    };
+
+    if broken > 0 && params.get_flag("fail-fast") {
+        CommandOutcome::failure(
+            format!(
+                "Haxe version {} is broken; stopped after checking {} Haxe versions",
+                results.last().unwrap().0.0,
+                results.len()
+            ),
+            2,
+        )
+    } else if broken > 0 {
+        CommandOutcome::failure(
+            format!(
+                "{} Haxe versions are ready to use, but {} are broken",
+                healthy, broken
+            ),
+            2,
+        )
+    } else if params.get_flag("json") || params.get_flag("porcelain") {
+        CommandOutcome::quiet()
+    } else {
+        CommandOutcome::success(format!("All {} Haxe versions are ready to use", healthy))
+    }
+    .with_output(output)
+}
 
-    if matches.subcommand_matches("check").is_some() {
-        check_config_validity(&config);
-        match config.as_ref().unwrap().0.get_path_installed() {
-            Ok(_) => {
-                *message = format!("Haxe version {} is ready to use", config.unwrap().0.0);
-                exit_code = 0;
-                force_exit_log = true;
+/// Checks the configured Haxe version.
+fn check_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let config: Config = required_config!(ctx);
+    let version: &HaxeVersion = &config.0;
+    match version
+        .get_path_installed()
+        .and_then(|path| match params.get_one::<String>("verify") {
+            Some(expected) => {
+                let checksum: String = version.checksum_binary()?;
+                if checksum.eq_ignore_ascii_case(expected) {
+                    Ok(path)
+                } else {
+                    Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Haxe compiler of version {} doesn't match the expected \
+                            checksum; expected {}, but found {}",
+                            version.0, expected, checksum
+                        ),
+                    ))
+                }
             }
-            Err(e) => {
-                *message = e.to_string();
-                exit_code = 2;
+            None => Ok(path),
+        }) {
+        Ok(_) if params.get_flag("porcelain") => {
+            CommandOutcome::quiet().with_output(vec![format!("{} installed", version.0)])
+        }
+        Ok(_) => CommandOutcome::success(match version.locate_std_path() {
+            Ok((path, StdSource::Environment)) => format!(
+                "Haxe version {} is ready to use, with the standard library \
+                at \"{}\" from HAXE_STD_PATH",
+                version.0,
+                display_path(&path)
+            ),
+            _ => format!("Haxe version {} is ready to use", version.0),
+        }),
+        Err(e) if params.get_flag("porcelain") => CommandOutcome::failure(e.to_string(), 2)
+            .with_output(vec![format!("{} missing", version.0)]),
+        Err(e) => CommandOutcome::failure(e.to_string(), 2),
+    }
+}
+
+/// Gathers the entries of the list subcommand, sorted from newest to oldest.
+///
+/// These are the versions of [`HaxeVersion::installed_sorted`], which is what
+/// list --porcelain shows, alongside the aliases of
+/// [`HaxeVersion::list_aliases`] with the version they point at.
+fn list_entries() -> Result<Vec<(HaxeVersion, Option<HaxeVersion>)>, Error> {
+    let mut entries: Vec<(HaxeVersion, Option<HaxeVersion>)> = HaxeVersion::installed_sorted()?
+        .into_iter()
+        .map(|version| (version, None))
+        .collect();
+    entries.extend(
+        HaxeVersion::list_aliases()?
+            .into_iter()
+            .map(|(alias, target)| (alias, Some(target))),
+    );
+    entries.sort_by(|(a, _), (b, _)| b.cmp(a));
+    Ok(entries)
+}
+
+/// Lists the installed Haxe versions in the stable format of list --porcelain.
+fn list_porcelain_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let active: Option<&HaxeVersion> = ctx.config.as_ref().map(|config| &config.0);
+    match HaxeVersion::installed_sorted() {
+        Ok(versions) => CommandOutcome::quiet().with_output(
+            narrow_list(params, versions, |version| version.clone())
+                .into_iter()
+                .map(|version| {
+                    format!(
+                        "{} {} {}",
+                        version.0,
+                        if active == Some(&version) {
+                            "active"
+                        } else {
+                            "-"
+                        },
+                        if version.is_complete().unwrap_or(false) {
+                            "installed"
+                        } else {
+                            "broken"
+                        }
+                    )
+                })
+                .collect(),
+        ),
+        Err(e) => CommandOutcome::failure(e.to_string(), 2),
+    }
+}
+
+/// Lists the installed Haxe versions for humans.
+///
+/// Aliases are marked, or followed by the version they point at when
+/// resolving aliases. Versions that are symlinks to somewhere outside of the
+/// installations directory are followed by that location instead.
+fn list_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let entries: Vec<(HaxeVersion, Option<HaxeVersion>)> = match list_entries() {
+        Ok(entries) => entries,
+        Err(e) => return CommandOutcome::failure(e.to_string(), 2),
+    };
+    let resolve: bool = params.get_flag("resolve-aliases");
+    // Entries are shown under their own names, since canonicalizing them would
+    // show aliases as the version they point at.
+    let root: PathBuf = match HaxeVersion::get_haxe_installations() {
+        Ok(root) => PathBuf::from(display_path(&root)),
+        Err(e) => return CommandOutcome::failure(e.to_string(), 2),
+    };
+    let mut output: Vec<String> = Vec::new();
+    for (version, target) in narrow_list(params, entries, |(version, _)| version.clone()) {
+        let path: String = root.join(&version.0).display().to_string();
+        let mut line: String = if version.is_complete().unwrap_or(false) {
+            paint(&path, Color::Green, ctx.color)
+        } else {
+            paint(&path, Color::Red, ctx.color)
+        };
+        match (resolve, target) {
+            (true, Some(target)) => line.push_str(&format!(" -> {}", target.0)),
+            (false, Some(_)) => line.push_str(" (alias)"),
+            (true, None) if version.is_symlink().unwrap_or(false) => {
+                if let Ok(link) = version.get_path().and_then(std::fs::read_link) {
+                    line.push_str(&format!(" -> {}", display_path(&link)));
+                }
             }
+            _ => {}
         }
-    } else if matches.subcommand_matches("list").is_some() {
-        match HaxeVersion::get_haxe_installations() {
-            Ok(path) => match read_dir(path) {
-                Ok(dir) => {
-                    let mut list: String = String::with_capacity(128);
-
-                    exit_code = 0;
-
-                    /// Tracks the list when listing all Haxe versions.
-                    macro_rules! track_list {
-                        ( $x: expr ) => {
-                            if let Some(data) = $x.to_str() {
-                                list.push_str(data);
-                            } else {
-                                *message = "Some directories were skipped because they used non-UTF-8 paths".into();
-                                exit_code = 0;
-                                force_exit_log = true;
-                            }
-                        };
-                    }
+        output.push(line);
+    }
+    CommandOutcome::quiet().with_output(output)
+}
 
-                    let parsed_dir: Vec<PathBuf> = dir
-                        .map(|res| res.map(|e| e.path()))
-                        .collect::<Result<Vec<_>, Error>>()
-                        .unwrap_or(vec![]);
-                    let mut iter: Iter<'_, PathBuf> = parsed_dir.iter();
-                    if let Some(first) = iter.next() {
-                        track_list!(first);
-                    }
-                    for next in iter {
-                        list.push('\n');
-                        track_list!(next);
+/// Prints the standard library path of the configured Haxe version.
+fn std_path_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let config: Config = required_config!(ctx);
+    let version: &HaxeVersion = &config.0;
+    match version
+        .get_path_installed()
+        .and_then(|_| version.get_std_path())
+    {
+        Ok(path) => CommandOutcome::quiet().with_output(vec![if params.get_flag("json") {
+            format!(
+                "{{\"version\":{},\"std_path\":{}}}",
+                json_string(&version.0),
+                json_string(&display_path(&path))
+            )
+        } else {
+            display_path(&path)
+        }]),
+        Err(e) => CommandOutcome::failure(e.to_string(), 2),
+    }
+}
+
+/// Prints the environment variables that executed programs get.
+fn env_command(ctx: &Context) -> CommandOutcome {
+    let config: Config = required_config!(ctx);
+    match config.0.env_vars() {
+        Ok(vars) => CommandOutcome::quiet().with_output(
+            vars.iter()
+                .map(|(key, value)| format!("{}={}", key, value.to_string_lossy()))
+                .collect(),
+        ),
+        Err(e) => CommandOutcome::failure(e.to_string(), 2),
+    }
+}
+
+/// Switches a configuration file to another Haxe version.
+fn switch_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let config_path: Option<&str> = if params.get_flag("local") {
+        Some(LOCAL_CONFIG)
+    } else {
+        ctx.config_path.as_deref()
+    };
+    let version: &String = params.get_one::<String>("HAXE_VERSION").unwrap();
+    let store: Result<(), Error> = if !HaxeVersion::is_valid_directory_name(version) {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "\"{}\" is not a valid Haxe version name",
+                version.escape_debug()
+            ),
+        ))
+    } else if params.get_flag("check-only") && params.get_flag("skip-check") {
+        Config::check_writable(config_path)
+    } else if params.get_flag("check-only") {
+        Config::check_write(config_path, version)
+    } else if params.get_flag("skip-check") {
+        Config::write(config_path, version)
+    } else {
+        Config::safe_write(config_path, version)
+    };
+    match store {
+        Ok(_) if params.get_flag("check-only") => CommandOutcome::success(format!(
+            "config \"{}\" can be switched to use Haxe version {}",
+            config_path.unwrap_or(DEFAULT_CONFIG),
+            version
+        )),
+        Err(e) if params.get_flag("check-only") => CommandOutcome::failure(
+            format!(
+                "config \"{}\" can't be switched to use Haxe version {}: {}",
+                config_path.unwrap_or(DEFAULT_CONFIG),
+                version,
+                e
+            ),
+            1,
+        ),
+        Ok(_) => CommandOutcome::success(format!(
+            "successfully switched config \"{}\" to use Haxe version {}",
+            config_path.unwrap_or(DEFAULT_CONFIG),
+            version
+        )),
+        Err(e) => CommandOutcome::failure(e.to_string(), 1),
+    }
+}
+
+/// Writes the configuration file created by init and config init.
+///
+/// An existing file is only replaced if `overwrite` is set. This returns the
+/// file that was targeted, and whether it was written.
+fn write_config(
+    path: Option<&str>,
+    version: &HaxeVersion,
+    overwrite: bool,
+) -> Result<(PathBuf, bool), Error> {
+    let target: PathBuf = Config::canonical_config_path(path);
+    if target.exists() && !overwrite {
+        return Ok((target, false));
+    }
+    match Config::write(path, &version.0) {
+        Ok(_) => Ok((target, true)),
+        Err(e) => Err(Error::new(
+            e.kind(),
+            format!("Could not create \"{}\": {}", display_path(&target), e),
+        )),
+    }
+}
+
+/// Creates a project, optionally from a template.
+fn init_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let template: Option<&String> = params.get_one::<String>("template");
+    let hxml: Option<&str> = template.and_then(|name| {
+        TEMPLATES
+            .iter()
+            .find(|(template, _)| template == name)
+            .map(|(_, hxml)| *hxml)
+    });
+    match (template, initial_version(&ctx.config)) {
+        (Some(name), _) if name == "list" => CommandOutcome::quiet()
+            .with_output(TEMPLATES.iter().map(|(name, _)| name.to_string()).collect()),
+        (Some(name), _) if hxml.is_none() => CommandOutcome::failure(
+            format!(
+                "Unknown template \"{}\"; run mask-hx init --template list \
+                to see the available templates",
+                name
+            ),
+            1,
+        ),
+        (_, Ok(version)) => {
+            let mut notes: Vec<String> = Vec::new();
+            let mut outcome: CommandOutcome = CommandOutcome::success(format!(
+                "initialized project using Haxe version {}",
+                version.0
+            ));
+            match write_config(ctx.config_path.as_deref(), &version, false) {
+                Ok((target, true)) => notes.push(format!("created \"{}\"", display_path(&target))),
+                Ok((target, false)) => notes.push(format!(
+                    "skipped \"{}\", since it already exists",
+                    display_path(&target)
+                )),
+                Err(e) => return CommandOutcome::failure(e.to_string(), 1),
+            }
+
+            let files: Vec<(&str, &str)> = match hxml {
+                Some(hxml) => vec![("build.hxml", hxml), ("src/Main.hx", TEMPLATE_MAIN)],
+                None => Vec::new(),
+            };
+            for (file, contents) in files {
+                let target: &Path = Path::new(file);
+                if target.exists() {
+                    notes.push(format!(
+                        "skipped \"{}\", since it already exists",
+                        display_path(target)
+                    ));
+                    continue;
+                }
+                match target
+                    .parent()
+                    .map_or(Ok(()), create_dir_all)
+                    .and_then(|_| write(target, contents))
+                {
+                    Ok(_) => notes.push(format!("created \"{}\"", display_path(target))),
+                    Err(e) => {
+                        outcome = CommandOutcome::failure(
+                            format!("Could not create \"{}\": {}", display_path(target), e),
+                            1,
+                        );
+                        break;
                     }
-                    println!("{}", list);
                 }
-                Err(e) => {
-                    *message = e.to_string();
-                    exit_code = 2;
+            }
+            outcome.with_notes(notes)
+        }
+        (_, Err(e)) => CommandOutcome::failure(e.to_string(), 1),
+    }
+}
+
+/// Writes a configuration file, as done by config init.
+fn config_init_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let version: HaxeVersion = match initial_version(&ctx.config) {
+        Ok(version) => version,
+        Err(e) => return CommandOutcome::failure(e.to_string(), 1),
+    };
+    match write_config(
+        ctx.config_path.as_deref(),
+        &version,
+        params.get_flag("force"),
+    ) {
+        Ok((target, true)) => CommandOutcome::success(format!(
+            "wrote \"{}\" using Haxe version {}",
+            display_path(&target),
+            version.0
+        )),
+        Ok((target, false)) => CommandOutcome::failure(
+            format!(
+                "\"{}\" already exists; pass --force to overwrite it",
+                display_path(&target)
+            ),
+            1,
+        ),
+        Err(e) => CommandOutcome::failure(e.to_string(), 1),
+    }
+}
+
+/// Moves a configuration file to a newer Haxe version.
+///
+/// The file that is rewritten is the one the configuration was read from,
+/// so running this in a subdirectory upgrades the closest `.mask` file.
+fn upgrade_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let path: String = match (&ctx.config_path, &ctx.config_file) {
+        (Some(named), _) => named.clone(),
+        (None, Some(found)) => found.to_string_lossy().into_owned(),
+        (None, None) => Config::default_path().to_string(),
+    };
+    let path: &str = &path;
+    let current: Config = match Config::new(Some(path)) {
+        Ok(current) => current,
+        Err(e) => return CommandOutcome::failure(e.to_string(), 1),
+    };
+    let target: Result<Option<HaxeVersion>, Error> = match params.get_one::<String>("to") {
+        Some(version) => {
+            let target: HaxeVersion = HaxeVersion(version.clone());
+            match target.get_path_installed() {
+                Err(e) => return CommandOutcome::failure(e.to_string(), 1),
+                Ok(_) if target <= current.0 => {
+                    return CommandOutcome::failure(
+                        format!(
+                            "Haxe version {} is not newer than {}, which config \"{}\" uses; \
+                            use switch to change to it anyway",
+                            target.0, current.0.0, path
+                        ),
+                        1,
+                    );
                 }
-            },
-            Err(e) => {
-                *message = e.to_string();
-                exit_code = 2;
+                Ok(_) => Ok(Some(target)),
             }
         }
-    } else if let Some(data) = matches.subcommand_matches("switch") {
-        let store: Result<(), Error> = if data.get_flag("skip-check") {
-            Config::write(
-                config_path.as_deref(),
-                data.get_one::<String>("HAXE_VERSION").unwrap(),
-            )
-        } else {
-            Config::safe_write(
-                config_path.as_deref(),
-                data.get_one::<String>("HAXE_VERSION").unwrap(),
+        None => current.0.next_installed(),
+    };
+    match target.and_then(|target| match target {
+        Some(target) => Config::safe_write(Some(path), &target.0).map(|_| Some(target)),
+        None => Ok(None),
+    }) {
+        Ok(Some(target)) => CommandOutcome::success(format!(
+            "successfully upgraded config \"{}\" from Haxe version {} to {}",
+            path, current.0.0, target.0
+        )),
+        Ok(None) => CommandOutcome::success(format!(
+            "config \"{}\" already uses the newest installed Haxe version {}",
+            path, current.0.0
+        )),
+        Err(e) => CommandOutcome::failure(e.to_string(), 1),
+    }
+}
+
+/// Packages a Haxe version into an archive.
+fn pack_command(params: &ArgMatches) -> CommandOutcome {
+    let version: HaxeVersion =
+        HaxeVersion(params.get_one::<String>("HAXE_VERSION").unwrap().clone());
+    let output: &String = params.get_one::<String>("OUTPUT").unwrap();
+    match version.pack(Path::new(output)) {
+        Ok(_) => CommandOutcome::success(format!(
+            "successfully packaged Haxe version {} into \"{}\"",
+            version.0, output
+        )),
+        Err(e) => CommandOutcome::failure(e.to_string(), 1),
+    }
+}
+
+/// Copies a Haxe version under another name.
+fn copy_command(params: &ArgMatches) -> CommandOutcome {
+    let version: HaxeVersion =
+        HaxeVersion(params.get_one::<String>("HAXE_VERSION").unwrap().clone());
+    let name: &String = params.get_one::<String>("NAME").unwrap();
+    match version.copy_to(name) {
+        Ok(copy) => CommandOutcome::success(format!(
+            "successfully copied Haxe version {} to {}",
+            version.0, copy.0
+        )),
+        Err(e) => CommandOutcome::failure(e.to_string(), 1),
+    }
+}
+
+/// Runs every [DoctorCheck], and prints their results.
+fn doctor_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let version: Option<&HaxeVersion> = ctx
+        .config
+        .as_ref()
+        .filter(|config| !config.is_empty())
+        .map(|config| &config.0);
+    let version_check: DoctorCheck = check_version(version);
+    let std_check: DoctorCheck = match version_check.status {
+        CheckStatus::Failure => DoctorCheck {
+            check: "std",
+            status: CheckStatus::Warning,
+            detail: "Skipped, since the Haxe version is not usable".to_string(),
+        },
+        _ => check_std(version),
+    };
+    let checks: [DoctorCheck; 4] = [
+        check_installations(),
+        check_config(ctx.config.as_ref(), ctx.config_error.as_ref()),
+        version_check,
+        std_check,
+    ];
+    let failed: usize = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Failure)
+        .count();
+
+    let output: Vec<String> = if params.get_flag("json") {
+        let entries: Vec<String> = checks
+            .iter()
+            .map(|check| {
+                format!(
+                    "{{\"check\":{},\"status\":{},\"detail\":{}}}",
+                    json_string(check.check),
+                    json_string(check.status.name()),
+                    json_string(&check.detail)
+                )
+            })
+            .collect();
+        vec![format!(
+            "{{\"checks\":[{}],\"ok\":{}}}",
+            entries.join(","),
+            failed == 0
+        )]
+    } else {
+        checks
+            .iter()
+            .map(|check| {
+                let status: String = match check.status {
+                    CheckStatus::Ok => paint("ok", Color::Green, ctx.color),
+                    CheckStatus::Warning => paint("warning", Color::Yellow, ctx.color),
+                    CheckStatus::Failure => paint("failure", Color::Red, ctx.color),
+                };
+                format!("[{}] {}: {}", status, check.check, check.detail)
+            })
+            .collect()
+    };
+
+    match failed {
+        0 if params.get_flag("json") => CommandOutcome::quiet(),
+        0 => CommandOutcome::success("No problems found".to_string()),
+        failed => CommandOutcome::failure(format!("{} checks failed", failed), 2),
+    }
+    .with_output(output)
+}
+
+/// Checks every configuration file in a directory tree.
+fn verify_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let dir: &String = params.get_one::<String>("DIR").unwrap();
+    let FoundConfigs { files, unreadable } = match Config::find_all(Path::new(dir)) {
+        Ok(found) => found,
+        Err(e) => {
+            return CommandOutcome::failure(format!("Could not search \"{}\": {}", dir, e), 1);
+        }
+    };
+    let mut output: Vec<String> = unreadable
+        .iter()
+        .map(|(dir, e)| {
+            format!(
+                "[{}] {}: {}",
+                paint("unreadable", Color::Red, ctx.color),
+                display_path(dir),
+                e
             )
+        })
+        .collect();
+    let mut broken: usize = 0;
+    for file in &files {
+        let problem: Option<String> = match Config::new(Some(&file.to_string_lossy())) {
+            Ok(config) if config.is_empty() => None,
+            Ok(config) => match config.0.resolve_chain() {
+                Ok((version, _)) => missing_version_message(&version).map(|_| {
+                    if version == config.0 {
+                        format!("Haxe version {} is not installed", version.0)
+                    } else {
+                        format!(
+                            "Haxe version {} resolves to {}, which is not installed",
+                            config.0.0, version.0
+                        )
+                    }
+                }),
+                Err(e) => Some(e.to_string()),
+            },
+            Err(e) => Some(e.to_string()),
         };
-        match store {
-            Ok(_) => {
-                *message = format!(
-                    "successfully switched config \"{}\" to use Haxe version {}",
-                    config_path.as_deref().unwrap_or(".mask"),
-                    data.get_one::<String>("HAXE_VERSION").unwrap()
-                );
-                exit_code = 0;
-                force_exit_log = true;
-            }
-            Err(e) => {
-                *message = e.to_string();
-                exit_code = 1;
+        match problem {
+            Some(problem) => {
+                output.push(format!(
+                    "[{}] {}: {}",
+                    paint("broken", Color::Red, ctx.color),
+                    display_path(file),
+                    problem
+                ));
+                broken += 1;
             }
+            None => output.push(format!(
+                "[{}] {}",
+                paint("ok", Color::Green, ctx.color),
+                display_path(file)
+            )),
         }
-    } else if let Some(params) = matches.subcommand_matches("exec") {
-        check_config_validity(&config);
-        let results: (String, i32) = match execute(params, config.unwrap(), "haxe") {
-            Ok(data) => data,
-            Err(e) => (format!("Execution error: {}", e), 1),
+    }
+
+    match (broken, unreadable.len()) {
+        (0, 0) => CommandOutcome::success(format!(
+            "All {} configuration files use installed Haxe versions",
+            files.len()
+        )),
+        (broken, 0) => CommandOutcome::failure(
+            format!(
+                "{} of {} configuration files use Haxe versions that aren't installed",
+                broken,
+                files.len()
+            ),
+            2,
+        ),
+        (broken, skipped) => CommandOutcome::failure(
+            format!(
+                "{} of {} configuration files use Haxe versions that aren't installed, \
+                and {} directories could not be searched",
+                broken,
+                files.len(),
+                skipped
+            ),
+            2,
+        ),
+    }
+    .with_output(output)
+}
+
+/// Records the installed Haxe versions in a lock file.
+fn lock_command(params: &ArgMatches) -> CommandOutcome {
+    let file: &String = params.get_one::<String>("FILE").unwrap();
+    let versions: Vec<HaxeVersion> = match HaxeVersion::installed_sorted() {
+        Ok(versions) => versions,
+        Err(e) => return CommandOutcome::failure(e.to_string(), 2),
+    };
+    let mut contents: String = String::new();
+    let mut warnings: Vec<String> = Vec::new();
+    for version in &versions {
+        match version.checksum_binary() {
+            Ok(checksum) => contents.push_str(&format!("{} {}\n", version.0, checksum)),
+            Err(e) => warnings.push(format!("Skipped Haxe version {}: {}", version.0, e)),
+        }
+    }
+    match write(file, contents) {
+        Ok(_) => CommandOutcome::success(format!("successfully wrote \"{}\"", file)),
+        Err(e) => CommandOutcome::failure(format!("Could not write \"{}\": {}", file, e), 1),
+    }
+    .with_warnings(warnings)
+}
+
+/// Compares the installed Haxe versions against a lock file.
+fn sync_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let file: &String = params.get_one::<String>("FILE").unwrap();
+    let contents: String = match read_to_string(file) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return CommandOutcome::failure(format!("Could not read \"{}\": {}", file, e), 1);
+        }
+    };
+    let mut mismatched: usize = 0;
+    let mut output: Vec<String> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // The checksum is split off at the last space, since versions may contain spaces.
+        let (version, expected): (HaxeVersion, &str) = match line.trim_end().rsplit_once(' ') {
+            Some((version, checksum)) if !version.trim().is_empty() && !checksum.is_empty() => {
+                (HaxeVersion(version.to_string()), checksum)
+            }
+            _ => {
+                return CommandOutcome::failure(
+                    format!("Lock file \"{}\" is malformed on line {}", file, index + 1),
+                    1,
+                )
+                .with_output(output)
+                .with_warnings(warnings);
+            }
         };
-        *message = results.0;
-        exit_code = results.1;
-    } else if let Some(params) = matches.subcommand_matches("lib") {
-        check_config_validity(&config);
-        let results: (String, i32) = match execute(params, config.unwrap(), "haxelib") {
-            Ok(data) => data,
-            Err(e) => (format!("Execution error: {}", e), 1),
+
+        let problem: Option<(&str, Option<String>)> = match missing_version_message(&version) {
+            Some(missing) => Some(("missing", Some(missing))),
+            None => match version.checksum_binary() {
+                Ok(checksum) if checksum.eq_ignore_ascii_case(expected) => None,
+                Ok(_) => Some(("mismatch", None)),
+                Err(e) => Some(("mismatch", Some(e.to_string()))),
+            },
         };
-        *message = results.0;
-        exit_code = results.1;
-    } else if let Some(params) = matches.subcommand_matches("run") {
-        check_config_validity(&config);
-        let args: Vec<String> = parse_args!(params);
-        let prog: &String = params.get_one::<String>("PROGRAM").unwrap();
-        match create_patched_cmd(args, config.clone().unwrap(), prog.into()) {
-            Ok(mut cmd) => {
-                match cmd
-                    .env("MASK_PATH_OVERRIDE", config.unwrap().0.0)
-                    .stdin(Stdio::inherit())
-                    .stdout(Stdio::inherit())
-                    .stderr(Stdio::inherit())
-                    .output()
-                {
-                    Ok(output) => {
-                        *message = exec_message!(output.status.code(), prog);
-                        exit_code = output.status.code().unwrap_or(143);
+        match problem {
+            Some((status, warning)) => {
+                output.push(format!(
+                    "[{}] {}",
+                    paint(status, Color::Red, ctx.color),
+                    version.0
+                ));
+                warnings.extend(warning);
+                mismatched += 1;
+            }
+            None => output.push(format!(
+                "[{}] {}",
+                paint("ok", Color::Green, ctx.color),
+                version.0
+            )),
+        }
+    }
+
+    if mismatched == 0 {
+        CommandOutcome::success(format!("All Haxe versions in \"{}\" are installed", file))
+    } else {
+        CommandOutcome::failure(
+            format!(
+                "{} Haxe versions in \"{}\" are missing or don't match",
+                mismatched, file
+            ),
+            2,
+        )
+        .with_warnings(warnings)
+    }
+    .with_output(output)
+}
+
+/// Runs the Haxe compiler, or another compiler given with --compiler.
+fn exec_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let config: Config = required_config!(ctx);
+    let compiler: Option<&String> = params.get_one::<String>("compiler");
+    let missing: Option<String> = match compiler {
+        Some(_) => None,
+        None => missing_version_message(&config.0),
+    };
+    let builder: ExecBuilder = ctx.exec_builder(config);
+    let dump: Option<(&str, bool)> = params
+        .get_one::<String>("dump-env")
+        .map(|file| (file.as_str(), ctx.inherits_env()));
+    match (missing, compiler) {
+        (Some(missing), _) => CommandOutcome::failure(missing, 2),
+        (None, Some(compiler)) => execute(
+            params,
+            builder.prog_path(compiler.into()),
+            compiler,
+            ctx.trace,
+            dump,
+        )
+        .unwrap_or_else(|e| CommandOutcome::failure(exec_error(compiler, &e), 1)),
+        (None, None) => execute(params, builder.prog("haxe"), "haxe", ctx.trace, dump)
+            .unwrap_or_else(|e| CommandOutcome::failure(exec_error("haxe", &e), 1)),
+    }
+}
+
+/// Builds a project from a `.hxml` file.
+fn build_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let config: Config = required_config!(ctx);
+    let hxml: &String = params.get_one::<String>("HXML").unwrap();
+    if !Path::new(hxml).is_file() {
+        return CommandOutcome::failure(format!("Build file \"{}\" does not exist", hxml), 1);
+    }
+    if let Some(missing) = missing_version_message(&config.0) {
+        return CommandOutcome::failure(missing, 2);
+    }
+    let args: Vec<String> = match injected_args(vec![hxml.clone()]) {
+        Ok(args) => args,
+        Err(e) => return CommandOutcome::failure(e.to_string(), 1),
+    };
+    match run_cmd(ctx.exec_builder(config).args(args), ctx.trace) {
+        Ok(status) => CommandOutcome::exited(
+            exec_message!(status.code(), "haxe"),
+            status.code().unwrap_or(143),
+        ),
+        Err(e) => CommandOutcome::failure(exec_error("haxe", &e), 1),
+    }
+}
+
+/// Runs haxelib, using the closest local repository unless --global is given.
+fn lib_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let config: Config = required_config!(ctx);
+    let mut args: Vec<String> = parse_args!(params);
+    let mut builder: ExecBuilder = ctx.exec_builder(config.clone());
+    if params.get_flag("global") {
+        args.insert(0, "--global".to_string());
+    } else if let Some(repo) = env::current_dir().ok().and_then(|dir| {
+        dir.ancestors()
+            .map(|dir| dir.join(".haxelib"))
+            .find(|repo| repo.is_dir())
+    }) {
+        builder = builder.env("HAXELIB_PATH", &repo.to_string_lossy());
+    }
+
+    if let Some(missing) = missing_version_message(&config.0) {
+        return CommandOutcome::failure(missing, 2);
+    }
+    match run_cmd(builder.prog("haxelib").args(args), ctx.trace) {
+        Ok(status) => CommandOutcome::exited(
+            exec_message!(status.code(), "haxelib"),
+            status.code().unwrap_or(143),
+        ),
+        Err(e) => CommandOutcome::failure(exec_error("haxelib", &e), 1),
+    }
+}
+
+/// Runs the Haxe compiler or haxelib with several Haxe versions in turn.
+fn matrix_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let versions: Result<Vec<HaxeVersion>, Error> = if params.get_flag("all") {
+        HaxeVersion::installed_sorted()
+    } else {
+        Ok(params
+            .get_many::<String>("VERSIONS")
+            .unwrap_or_default()
+            .map(|version| HaxeVersion(version.clone()))
+            .collect())
+    };
+    let program: Result<(&str, Vec<String>), Error> = match params.subcommand() {
+        Some(("lib", sub)) => Ok(("haxelib", parse_args!(sub))),
+        Some((_, sub)) => injected_args(parse_args!(sub)).map(|args| ("haxe", args)),
+        None => unreachable!("a subcommand is required"),
+    };
+    let (versions, prog, args): (Vec<HaxeVersion>, &str, Vec<String>) = match (versions, program) {
+        (_, Err(e)) => return CommandOutcome::failure(e.to_string(), 1),
+        (Err(e), _) => return CommandOutcome::failure(e.to_string(), 2),
+        (Ok(versions), Ok((prog, args))) => (versions, prog, args),
+    };
+
+    let mut results: Vec<(HaxeVersion, &str)> = Vec::with_capacity(versions.len());
+    let mut warnings: Vec<String> = Vec::new();
+    for version in versions {
+        let status: &str = if !warnings.is_empty() && !params.get_flag("keep-going") {
+            "skipped"
+        } else {
+            progress(&format!("running with Haxe version {}", version.0));
+            let builder: ExecBuilder = ctx
+                .exec_builder(Config(version.clone()))
+                .prog(prog)
+                .args(args.clone());
+            match run_cmd(builder, ctx.trace) {
+                Ok(status) if status.success() => "passed",
+                Ok(status) => {
+                    warnings.push(format!(
+                        "Haxe version {}: {}",
+                        version.0,
+                        exec_message!(status.code(), prog)
+                    ));
+                    "failed"
+                }
+                Err(e) => {
+                    warnings.push(format!(
+                        "Haxe version {}: {}",
+                        version.0,
+                        exec_error(prog, &e)
+                    ));
+                    "failed"
+                }
+            }
+        };
+        results.push((version, status));
+    }
+    let output: Vec<String> = results
+        .iter()
+        .map(|(version, status)| {
+            let shade: Color = match *status {
+                "passed" => Color::Green,
+                "failed" => Color::Red,
+                _ => Color::Yellow,
+            };
+            format!("[{}] {}", paint(status, shade, ctx.color), version.0)
+        })
+        .collect();
+
+    if warnings.is_empty() {
+        CommandOutcome::success(format!("All {} Haxe versions passed", results.len()))
+    } else {
+        CommandOutcome::failure(
+            format!(
+                "{} of {} Haxe versions failed",
+                warnings.len(),
+                results.len()
+            ),
+            1,
+        )
+        .with_warnings(warnings)
+    }
+    .with_output(output)
+}
+
+/// Runs any program with the configured Haxe version on the `PATH`.
+fn run_command(ctx: &Context, params: &ArgMatches) -> CommandOutcome {
+    let config: Config = required_config!(ctx);
+    let args: Vec<String> = parse_args!(params);
+    let prog: &String = params.get_one::<String>("PROGRAM").unwrap();
+    let version: String = config.0.0.clone();
+    let builder: ExecBuilder = match config.0.neko_path() {
+        Ok(Some(neko)) if prog == "neko" => ctx.exec_builder(config).prog_path(neko),
+        _ => ctx.exec_builder(config).external_prog(prog),
+    };
+    match run_cmd(
+        builder.args(args).env("MASK_PATH_OVERRIDE", &version),
+        ctx.trace,
+    ) {
+        Ok(status) => CommandOutcome::exited(
+            exec_message!(status.code(), prog),
+            status.code().unwrap_or(143),
+        ),
+        Err(e) => CommandOutcome::failure(exec_error(prog, &e), 1),
+    }
+}
+
+/// Gathers the [Context] that subcommands run with.
+///
+/// This changes into the directory given with --cwd first, since the
+/// configuration is resolved relative to it.
+fn context(matches: &ArgMatches) -> Result<Context<'_>, Box<CommandOutcome>> {
+    let trace: bool = matches.get_flag("trace");
+    let color: bool = color_enabled(matches);
+
+    if let Some(dir) = matches.get_one::<String>("cwd") {
+        if !Path::new(dir).is_dir() {
+            return Err(Box::new(CommandOutcome::failure(
+                format!("Directory \"{}\" does not exist", dir),
+                2,
+            )));
+        }
+        if let Err(e) = env::set_current_dir(dir) {
+            return Err(Box::new(CommandOutcome::failure(
+                format!("Could not change to directory \"{}\": {}", dir, e),
+                2,
+            )));
+        }
+    }
+    let options: ResolveOptions = ResolveOptions {
+        explicit: matches.get_one::<String>("explicit").cloned(),
+        config: matches.get_one::<String>("config").cloned(),
+    };
+    let config_path: Option<String> = options
+        .named_config(|key| env::var(key).ok())
+        .map(|(file, _)| file);
+    let (config, config_error, config_file): (Option<Config>, Option<Error>, Option<PathBuf>) =
+        match env::current_dir()
+            .and_then(|dir| Config::resolve(&dir, |key| env::var(key).ok(), &options))
+        {
+            Ok(resolved) => {
+                if trace {
+                    eprintln!("mask-hx: trace: config source: {}", resolved.source);
+                    if let Some(path) = &resolved.path {
+                        eprintln!("mask-hx: trace: config path: {}", display_path(path));
                     }
-                    Err(e) => {
-                        *message = e.to_string();
-                        exit_code = 1;
+                    if let Some(requested) = &resolved.requested {
+                        eprintln!("mask-hx: trace: requested version: {}", requested.0);
+                    }
+                    for step in &resolved.steps {
+                        eprintln!("mask-hx: trace: resolution step: {}", step);
                     }
                 }
+                (resolved.config, None, resolved.path)
             }
-            Err(e) => {
-                *message = e.to_string();
-                exit_code = 1;
-            }
+            Err(e) => (None, Some(e), None),
+        };
+
+    if trace {
+        match &config {
+            Some(data) => eprintln!("mask-hx: trace: resolved version: {}", data.0.0),
+            None => eprintln!("mask-hx: trace: resolved version: none"),
+        }
+        if let Some(e) = &config_error {
+            eprintln!("mask-hx: trace: config error: {}", e);
+        }
+        match HaxeVersion::get_haxe_installations() {
+            Ok(path) => eprintln!(
+                "mask-hx: trace: installations root: {}",
+                display_path(&path)
+            ),
+            Err(e) => eprintln!("mask-hx: trace: installations root: {}", e),
         }
+    }
+
+    Ok(Context {
+        matches,
+        config,
+        config_error,
+        config_path,
+        config_file,
+        trace,
+        color,
+    })
+}
+
+/// Runs the subcommand given in the arguments, and returns its outcome.
+fn run(matches: &ArgMatches) -> CommandOutcome {
+    let ctx: Context = match context(matches) {
+        Ok(ctx) => ctx,
+        Err(outcome) => return *outcome,
     };
+    match matches.subcommand() {
+        Some(("check", params)) if params.get_flag("all") => check_all_command(&ctx, params),
+        Some(("check", params)) => check_command(&ctx, params),
+        Some(("list", params)) if params.get_flag("porcelain") => {
+            list_porcelain_command(&ctx, params)
+        }
+        Some(("list", params)) => list_command(&ctx, params),
+        Some(("std-path", params)) => std_path_command(&ctx, params),
+        Some(("env", _)) => env_command(&ctx),
+        Some(("switch", params)) => switch_command(&ctx, params),
+        Some(("init", params)) => init_command(&ctx, params),
+        Some(("config", params)) => match params.subcommand() {
+            Some(("init", params)) => config_init_command(&ctx, params),
+            _ => unreachable!("a subcommand is required"),
+        },
+        Some(("upgrade", params)) => upgrade_command(&ctx, params),
+        Some(("pack", params)) => pack_command(params),
+        Some(("copy", params)) => copy_command(params),
+        Some(("doctor", params)) => doctor_command(&ctx, params),
+        Some(("verify", params)) => verify_command(&ctx, params),
+        Some(("lock", params)) => lock_command(params),
+        Some(("sync", params)) => sync_command(&ctx, params),
+        Some(("exec", params)) => exec_command(&ctx, params),
+        Some(("build", params)) => build_command(&ctx, params),
+        Some(("lib", params)) => lib_command(&ctx, params),
+        Some(("matrix", params)) => matrix_command(&ctx, params),
+        Some(("run", params)) => run_command(&ctx, params),
+        _ => CommandOutcome::failure(
+            "Invalid subcommand or no subcommand was passed; try running mask-hx help".to_string(),
+            1,
+        ),
+    }
+}
+
+/// The entry point of the program.
+///
+/// This handles the arguments, and reports the [CommandOutcome] of the
+/// subcommand that was run.
+fn main() {
+    // Rust ignores SIGPIPE, which makes printing into a closed pipe panic, as
+    // in mask-hx list | head; restoring the default exits quietly instead.
+    #[cfg(unix)]
+    // SAFETY: No other threads exist yet, and SIG_DFL is always a valid handler.
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+
+    run(&handle_commands().get_matches()).finish();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Serializes the tests, since they share the environment and the working directory.
+    static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+    /// Sets up an installations directory holding Haxe version 4.3.7, and
+    /// clears every variable that changes how `mask-hx` behaves.
+    ///
+    /// The returned guard has to be held for the whole test.
+    fn fixture() -> (MutexGuard<'static, ()>, PathBuf) {
+        let guard: MutexGuard<'static, ()> = ENVIRONMENT
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let root: PathBuf = env::temp_dir().join("mask-hx-tests");
+        let version: PathBuf = root.join("versions").join("4.3.7");
+        create_dir_all(version.join("std")).unwrap();
+        for program in ["haxe", "haxelib"] {
+            let path: PathBuf = version.join(executable_name(program));
+            write(&path, "#!/bin/sh\nexit 0\n").unwrap();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            }
+        }
+        // SAFETY: The guard keeps other tests from accessing the environment.
+        unsafe {
+            env::set_var("MASK_HAXE_DIR", root.join("versions"));
+            for var in VERSION_VARS.iter().chain(&[
+                "MASK_CONFIG",
+                "MASK_CONFIG_FILE",
+                "MASK_EXEC_ARGS",
+                "MASK_EXEC_WRAPPER",
+                "MASK_STD_SUBDIR",
+                "HAXE_STD_PATH",
+            ]) {
+                env::remove_var(var);
+            }
+        }
+        (guard, root)
+    }
+
+    /// Runs `mask-hx` with the given arguments, without exiting.
+    fn run_with(args: &[&str]) -> CommandOutcome {
+        let matches: ArgMatches = handle_commands()
+            .try_get_matches_from(std::iter::once("mask-hx").chain(args.iter().copied()))
+            .unwrap();
+        run(&matches)
+    }
+
+    #[test]
+    fn missing_cwd_fails_before_running() {
+        let (_guard, root) = fixture();
+        let missing: PathBuf = root.join("missing");
+        let outcome: CommandOutcome = run_with(&["--cwd", &missing.to_string_lossy(), "check"]);
+        assert_eq!(outcome.code, 2);
+        assert_eq!(
+            outcome.stderr,
+            Some(format!(
+                "Directory \"{}\" does not exist",
+                missing.display()
+            ))
+        );
+    }
+
+    #[test]
+    fn required_config_reports_missing_configuration() {
+        let (_guard, _) = fixture();
+        let matches: ArgMatches = handle_commands()
+            .try_get_matches_from(["mask-hx", "check"])
+            .unwrap();
+        let ctx: Context = Context {
+            matches: &matches,
+            config: None,
+            config_error: None,
+            config_path: None,
+            config_file: None,
+            trace: false,
+            color: false,
+        };
+        let outcome: CommandOutcome = *ctx.required_config().err().unwrap();
+        assert_eq!(outcome.code, 2);
+        assert!(outcome.stderr.unwrap().starts_with("Impossible"));
+
+        let ctx: Context = Context {
+            config_error: Some(Error::new(ErrorKind::PermissionDenied, "unreadable")),
+            ..ctx
+        };
+        let outcome: CommandOutcome = *ctx.required_config().err().unwrap();
+        assert_eq!(outcome.code, 1);
+        assert_eq!(outcome.stderr.as_deref(), Some("unreadable"));
+    }
 
-    if force_exit_log {
-        println!("mask-hx: {}", *message);
-    } else if exit_code != 0 {
-        eprintln!("mask-hx: {}", *message);
+    #[test]
+    fn switch_rejects_invalid_names() {
+        let (_guard, root) = fixture();
+        let config: PathBuf = root.join("switch.mask");
+        let outcome: CommandOutcome = run_with(&[
+            "-c",
+            &config.to_string_lossy(),
+            "switch",
+            "--skip-check",
+            "../4.3.7",
+        ]);
+        assert_eq!(outcome.code, 1);
+        assert!(
+            outcome
+                .stderr
+                .unwrap()
+                .contains("not a valid Haxe version name")
+        );
+        assert!(!config.exists());
     }
 
-    exit(exit_code);
+    #[test]
+    fn upgrade_rewrites_the_closest_config() {
+        let (_guard, root) = fixture();
+        let project: PathBuf = root.join("upgrade");
+        create_dir_all(project.join("sub")).unwrap();
+        write(project.join(DEFAULT_CONFIG), "4.2.5").unwrap();
+        let previous: PathBuf = env::current_dir().unwrap();
+        let outcome: CommandOutcome = run_with(&[
+            "--cwd",
+            &project.join("sub").to_string_lossy(),
+            "upgrade",
+            "--to",
+            "4.3.7",
+        ]);
+        env::set_current_dir(previous).unwrap();
+        assert_eq!(outcome.code, 0, "{:?}", outcome.stderr);
+        assert_eq!(
+            read_to_string(project.join(DEFAULT_CONFIG)).unwrap().trim(),
+            "4.3.7"
+        );
+        assert!(!project.join("sub").join(DEFAULT_CONFIG).exists());
+        std::fs::remove_dir_all(project).unwrap();
+    }
+
+    #[test]
+    fn list_entries_only_holds_versions_and_aliases() {
+        let (_guard, root) = fixture();
+        let versions: PathBuf = root.join("versions");
+        write(versions.join("stray.txt"), "").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("4.3.7", versions.join("stable")).unwrap();
+        let entries: Vec<(HaxeVersion, Option<HaxeVersion>)> = list_entries().unwrap();
+        std::fs::remove_file(versions.join("stray.txt")).unwrap();
+        #[cfg(unix)]
+        std::fs::remove_file(versions.join("stable")).unwrap();
+
+        let names: Vec<(&str, Option<&str>)> = entries
+            .iter()
+            .map(|(version, target)| (version.0.as_str(), target.as_ref().map(|t| t.0.as_str())))
+            .collect();
+        #[cfg(unix)]
+        assert_eq!(names, [("stable", Some("4.3.7")), ("4.3.7", None)]);
+        #[cfg(not(unix))]
+        assert_eq!(names, [("4.3.7", None)]);
+    }
+
+    /// Narrows down a list of versions with the arguments of the list subcommand.
+    fn narrowed(args: &[&str]) -> Vec<String> {
+        let versions: Vec<HaxeVersion> = ["5.0.0", "4.3.7", "4.3.6-nightly", "4.3.6", "4.2.5"]
+            .map(|version| HaxeVersion(version.to_string()))
+            .into();
+        let matches: ArgMatches = handle_commands()
+            .try_get_matches_from(["mask-hx", "list"].iter().chain(args))
+            .unwrap();
+        let params: &ArgMatches = matches.subcommand_matches("list").unwrap();
+        narrow_list(params, versions, |version| version.clone())
+            .into_iter()
+            .map(|version| version.0)
+            .collect()
+    }
+
+    #[test]
+    fn narrow_list_filters_before_limiting() {
+        assert_eq!(
+            narrowed(&["--filter", "4.3.*", "--limit", "2"]),
+            ["4.3.7", "4.3.6-nightly"]
+        );
+        assert_eq!(
+            narrowed(&["--limit", "2", "--filter", "nightly"]),
+            ["4.3.6-nightly"]
+        );
+        assert_eq!(narrowed(&["--limit", "2"]), ["5.0.0", "4.3.7"]);
+    }
+
+    #[test]
+    fn narrow_list_limit_beyond_the_filtered_versions() {
+        assert_eq!(
+            narrowed(&["--filter", "4.3", "--limit", "10"]),
+            ["4.3.7", "4.3.6-nightly", "4.3.6"]
+        );
+        assert!(narrowed(&["--filter", "3.*", "--limit", "10"]).is_empty());
+    }
+
+    #[test]
+    fn narrow_list_limit_of_zero_lists_nothing() {
+        assert!(narrowed(&["--limit", "0"]).is_empty());
+        assert!(narrowed(&["--filter", "4.3.*", "--limit", "0"]).is_empty());
+    }
+
+    #[test]
+    fn check_all_summarizes_versions() {
+        let (_guard, _) = fixture();
+        let outcome: CommandOutcome = run_with(&["check", "--all", "--porcelain"]);
+        assert_eq!(outcome.code, 0);
+        assert_eq!(outcome.stderr, None);
+        assert_eq!(outcome.output, ["4.3.7 installed"]);
+        let outcome: CommandOutcome = run_with(&["check", "--all"]);
+        assert_eq!(
+            outcome.stdout.as_deref(),
+            Some("All 1 Haxe versions are ready to use")
+        );
+    }
+
+    #[test]
+    fn std_path_is_returned_as_output() {
+        let (_guard, root) = fixture();
+        let outcome: CommandOutcome = run_with(&["-e", "4.3.7", "std-path"]);
+        assert_eq!(outcome.code, 0);
+        assert_eq!(outcome.stdout, None);
+        assert_eq!(
+            outcome.output,
+            [display_path(
+                &root.join("versions").join("4.3.7").join("std")
+            )]
+        );
+
+        let outcome: CommandOutcome = run_with(&["-e", "4.2.5", "check", "--porcelain"]);
+        assert_eq!(outcome.code, 2);
+        assert_eq!(outcome.output, ["4.2.5 missing"]);
+    }
+
+    #[test]
+    fn lock_reports_skipped_versions_as_warnings() {
+        let (_guard, root) = fixture();
+        let broken: PathBuf = root.join("versions").join("4.2.5");
+        create_dir_all(broken.join("std")).unwrap();
+        let lock: PathBuf = root.join("versions.lock");
+        let outcome: CommandOutcome = run_with(&["lock", &lock.to_string_lossy()]);
+        std::fs::remove_dir_all(&broken).unwrap();
+        assert_eq!(outcome.code, 0);
+        assert_eq!(outcome.warnings.len(), 1);
+        assert!(outcome.warnings[0].starts_with("Skipped Haxe version 4.2.5"));
+        assert!(read_to_string(&lock).unwrap().starts_with("4.3.7 "));
+    }
+
+    #[test]
+    fn explicit_version_overrides_the_configuration() {
+        let (_guard, root) = fixture();
+        let config: PathBuf = root.join("explicit.mask");
+        write(&config, "4.2.5\n").unwrap();
+        let config: &str = &config.to_string_lossy();
+        assert_ne!(run_with(&["-c", config, "exec"]).code, 0);
+
+        let outcome: CommandOutcome = run_with(&["-c", config, "-e", "4.3.7", "check"]);
+        assert_eq!(outcome.code, 0);
+        assert_eq!(
+            outcome.stdout,
+            Some("Haxe version 4.3.7 is ready to use".to_string())
+        );
+        assert_eq!(run_with(&["-c", config, "-e", "4.3.7", "exec"]).code, 0);
+        assert_eq!(
+            run_with(&["-c", config, "-e", "4.3.7", "lib", "list"]).code,
+            0
+        );
+    }
+
+    #[test]
+    fn sync_accepts_locked_versions_with_spaces() {
+        let (_guard, root) = fixture();
+        let spaced: PathBuf = root.join("versions").join("4.3.7 nightly");
+        create_dir_all(spaced.join("std")).unwrap();
+        std::fs::copy(
+            root.join("versions")
+                .join("4.3.7")
+                .join(executable_name("haxe")),
+            spaced.join(executable_name("haxe")),
+        )
+        .unwrap();
+        let lock: PathBuf = root.join("spaced.lock");
+        let lock: &str = &lock.to_string_lossy();
+        let locked: CommandOutcome = run_with(&["lock", lock]);
+        let synced: CommandOutcome = run_with(&["sync", lock]);
+        std::fs::remove_dir_all(&spaced).unwrap();
+        assert_eq!(locked.code, 0);
+        assert!(read_to_string(lock).unwrap().contains("4.3.7 nightly "));
+        assert_eq!(synced.code, 0);
+    }
+
+    #[test]
+    fn blank_configuration_has_no_version() {
+        let (_guard, root) = fixture();
+        let config: PathBuf = root.join("blank.mask");
+        write(&config, "  \t\n\n").unwrap();
+        let outcome: CommandOutcome = run_with(&["-c", &config.to_string_lossy(), "check"]);
+        assert_eq!(outcome.code, 2);
+        assert_eq!(
+            outcome.stderr,
+            Some("No Haxe version specified".to_string())
+        );
+    }
+
+    #[test]
+    fn upgrade_rejects_targets_that_are_not_newer() {
+        let (_guard, root) = fixture();
+        let older: PathBuf = root.join("versions").join("4.2.5");
+        create_dir_all(older.join("std")).unwrap();
+        let config: PathBuf = root.join("upgrade.mask");
+        write(&config, "4.3.7\n").unwrap();
+        let config: &str = &config.to_string_lossy();
+        let downgrade: CommandOutcome = run_with(&["-c", config, "upgrade", "--to", "4.2.5"]);
+        let same: CommandOutcome = run_with(&["-c", config, "upgrade", "--to", "4.3.7"]);
+        let missing: CommandOutcome = run_with(&["-c", config, "upgrade", "--to", "9.9.9"]);
+        std::fs::remove_dir_all(&older).unwrap();
+        assert_eq!(downgrade.code, 1);
+        assert!(
+            downgrade
+                .stderr
+                .unwrap()
+                .starts_with("Haxe version 4.2.5 is not newer than 4.3.7")
+        );
+        assert_eq!(same.code, 1);
+        assert_eq!(missing.code, 1);
+        assert_eq!(read_to_string(config).unwrap(), "4.3.7\n");
+    }
+
+    #[test]
+    fn run_reports_programs_that_could_not_be_started() {
+        let (_guard, _) = fixture();
+        let outcome: CommandOutcome = run_with(&["-e", "4.3.7", "run", "mask-hx-missing-program"]);
+        assert_eq!(outcome.code, 1);
+        assert!(
+            outcome
+                .stderr
+                .unwrap()
+                .starts_with("\"mask-hx-missing-program\" could not be started: ")
+        );
+    }
 }